@@ -0,0 +1,518 @@
+//! Procedural macros for embedding Prism Assembly Language in Rust code\
+//! The source code is assembled at compile time, syntax errors are reported at the macro call site
+
+use std::collections::HashMap;
+
+use libnewton::addressing::AddressingMode;
+use libnewton::arrays::{Array2, Array3};
+use libnewton::assembler;
+use libnewton::binary;
+use libnewton::codes::{DelayCode, EffectCode};
+use libnewton::error::{Location, Position};
+use libnewton::instruction::InstructionSet;
+use libnewton::registers::Register;
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+
+/// Rust expression written as `{expr}` in place of an operand
+struct Interpolation {
+    /// Index of the instruction where it appears
+    instruction: usize,
+    /// Operand and array element (0 if it is not an array) where it appears
+    slot: (usize, usize),
+    expression: TokenStream,
+    span: Span,
+}
+
+/// Prism Assembly Language source code rebuilt from the macro tokens
+struct Source {
+    text: String,
+    /// Position of the next character in [text]
+    position: Position,
+    /// Position where every token was written and its original span
+    spans: Vec<(Position, Span)>,
+    interpolations: Vec<Interpolation>,
+    /// Line of the last token in the original source, new lines end an instruction
+    line: Option<usize>,
+    /// Whether the next token starts an instruction (or label)
+    start: bool,
+    /// Index of the current instruction (plus one) and its mnemonic
+    instructions: usize,
+    mnemonic: String,
+    /// Index of the next operand of the current instruction
+    operand: usize,
+    /// The current array holds registers (one of its elements is written as `$name`)
+    indirect: bool,
+}
+
+impl Source {
+    /// Rebuild the source code, instructions are separated by `;` or new lines
+    fn new(input: TokenStream) -> Result<Self, TokenStream> {
+        let mut source = Self {
+            text: String::new(),
+            position: Position { line: 1, column: 1 },
+            spans: vec![],
+            interpolations: vec![],
+            line: None,
+            start: true,
+            instructions: 0,
+            mnemonic: String::new(),
+            operand: 0,
+            indirect: false,
+        };
+
+        source.statements(input)?;
+        Ok(source)
+    }
+
+    /// Append text to the source code, written at the position of [span]
+    fn write(&mut self, text: &str, span: Span) {
+        self.spans.push((self.position, span));
+        self.append(text);
+    }
+
+    /// Append text to the source code without any span
+    fn append(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else {
+                self.position.column += 1;
+            }
+        }
+        self.text.push_str(text);
+    }
+
+    /// Top level tokens, a sequence of labels and instructions
+    fn statements(&mut self, input: TokenStream) -> Result<(), TokenStream> {
+        let mut tokens = input.into_iter().peekable();
+
+        while let Some(token) = tokens.next() {
+            // Tokens in another line start a new instruction
+            let line = token.span().start().line;
+            if self.line.is_some_and(|previous| previous != line) {
+                self.append("\n");
+                self.start = true;
+            }
+            self.line = Some(token.span().end().line);
+            self.append(" ");
+
+            match &token {
+                TokenTree::Punct(punct) if punct.as_char() == ';' => {
+                    self.write(";", punct.span());
+                    self.start = true;
+                }
+                TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                    self.write(",", punct.span());
+                }
+                // Labels do not start an instruction
+                TokenTree::Ident(ident) if self.start => {
+                    self.write(&ident.to_string(), ident.span());
+                    if let Some(TokenTree::Punct(colon)) = tokens.peek() {
+                        if colon.as_char() == ':' {
+                            self.write(":", colon.span());
+                            tokens.next();
+                            continue;
+                        }
+                    }
+
+                    self.start = false;
+                    self.instructions += 1;
+                    self.mnemonic = ident.to_string().to_ascii_uppercase();
+                    self.operand = 0;
+                }
+                _ => {
+                    self.value(token, &mut tokens, 0)?;
+                    self.operand += 1;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Tokens of an operand (or array element), registers take the next token as their name
+    fn value(
+        &mut self,
+        token: TokenTree,
+        tokens: &mut impl Iterator<Item = TokenTree>,
+        element: usize,
+    ) -> Result<(), TokenStream> {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => {
+                self.interpolate(group.stream(), group.span(), element)
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Bracket => {
+                self.write("[", group.span_open());
+                // Interpolated elements take the addressing mode of the others
+                self.indirect = group.stream().into_iter().any(
+                    |token| matches!(token, TokenTree::Punct(punct) if punct.as_char() == '$'),
+                );
+                let mut tokens = group.stream().into_iter();
+                let mut element = 0;
+                while let Some(token) = tokens.next() {
+                    self.append(" ");
+                    match token {
+                        TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                            self.write(",", punct.span());
+                            element += 1;
+                        }
+                        token => self.value(token, &mut tokens, element)?,
+                    }
+                }
+                self.write("]", group.span_close());
+                self.indirect = false;
+                Ok(())
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                self.write("$", punct.span());
+                if let Some(name) = tokens.next() {
+                    self.write(&name.to_string(), name.span());
+                }
+                Ok(())
+            }
+            token => {
+                self.write(&token.to_string(), token.span());
+                Ok(())
+            }
+        }
+    }
+
+    /// Replace an interpolated expression with a placeholder operand
+    fn interpolate(
+        &mut self,
+        expression: TokenStream,
+        span: Span,
+        element: usize,
+    ) -> Result<(), TokenStream> {
+        if self.start {
+            return Err(error(span, "expected an instruction"));
+        }
+
+        let operand = self.operand;
+        let placeholder = match (self.mnemonic.as_str(), operand) {
+            // Codes and branching targets are not addressable
+            ("EFFECT" | "DELAY" | "JMP", 0) | ("BEQ" | "BNE", 2) => {
+                return Err(error(
+                    span,
+                    "only immediates and registers can be interpolated",
+                ))
+            }
+            // Parameters that only accept registers
+            ("LOAD" | "ADD" | "SUB" | "GET", 0) => "$0",
+            _ if self.indirect => "$0",
+            _ => "0",
+        };
+
+        self.write(placeholder, span);
+        self.interpolations.push(Interpolation {
+            instruction: self.instructions - 1,
+            slot: (operand, element),
+            expression,
+            span,
+        });
+        Ok(())
+    }
+
+    /// Span of the token written at the given position of the source code
+    fn span(&self, position: Position) -> Span {
+        self.spans
+            .iter()
+            .take_while(|(start, _)| (start.line, start.column) <= (position.line, position.column))
+            .last()
+            .map_or_else(Span::call_site, |&(_, span)| span)
+    }
+
+    /// Parse the source code, errors are reported at the span of the offending token
+    fn parse(&self) -> Result<Vec<(Position, InstructionSet)>, TokenStream> {
+        assembler::parse_located(&self.text).map_err(|e| {
+            let span = match e.location() {
+                Some(Location::Source(position)) => self.span(position),
+                _ => Span::call_site(),
+            };
+            error(span, &e.kind().to_string())
+        })
+    }
+}
+
+/// Compile error at the given span
+fn error(span: Span, message: &str) -> TokenStream {
+    quote_spanned!(span=> ::core::compile_error!(#message))
+}
+
+/// Build a register
+fn register(register: Register) -> TokenStream {
+    match register {
+        Register::SC => quote!(::libnewton::registers::Register::SC),
+        Register::SF => quote!(::libnewton::registers::Register::SF),
+        Register::PC => quote!(::libnewton::registers::Register::PC),
+        Register::PP => quote!(::libnewton::registers::Register::PP),
+        Register::RV => quote!(::libnewton::registers::Register::RV),
+        Register::R0 => quote!(::libnewton::registers::Register::R0),
+        Register::R1 => quote!(::libnewton::registers::Register::R1),
+        Register::PO => quote!(::libnewton::registers::Register::PO),
+        Register::GeneralPurpose(n) => {
+            let n = n.number();
+            quote!(::libnewton::registers::Register::general(#n).expect("checked by prism!"))
+        }
+    }
+}
+
+/// Builds operands, replacing the placeholders of interpolated expressions
+struct Operands<'a> {
+    interpolations: HashMap<(usize, usize), &'a TokenStream>,
+}
+
+impl Operands<'_> {
+    /// Build the register at the given slot
+    fn register(&self, slot: (usize, usize), value: Register) -> TokenStream {
+        match self.interpolations.get(&slot) {
+            Some(expression) => {
+                quote!({ let register: ::libnewton::registers::Register = #expression; register })
+            }
+            None => register(value),
+        }
+    }
+
+    /// Build the addressing mode at the given slot
+    fn addressing(&self, slot: (usize, usize), value: AddressingMode) -> TokenStream {
+        match (self.interpolations.get(&slot), value) {
+            (Some(expression), _) => {
+                quote!(::libnewton::addressing::AddressingMode::from(#expression))
+            }
+            (None, AddressingMode::Immediate(value)) => {
+                quote!(::libnewton::addressing::AddressingMode::Immediate(#value))
+            }
+            (None, AddressingMode::Indirect(value)) => {
+                let value = register(value);
+                quote!(::libnewton::addressing::AddressingMode::Indirect(#value))
+            }
+        }
+    }
+
+    /// Build the array of two elements at the given operand
+    fn array2(&self, operand: usize, Array2(a, b): Array2<AddressingMode>) -> TokenStream {
+        let a = self.addressing((operand, 0), a);
+        let b = self.addressing((operand, 1), b);
+        quote!(::libnewton::arrays::Array2(#a, #b))
+    }
+
+    /// Build the array of three elements at the given operand
+    fn array3(&self, operand: usize, Array3(a, b, c): Array3<AddressingMode>) -> TokenStream {
+        let a = self.addressing((operand, 0), a);
+        let b = self.addressing((operand, 1), b);
+        let c = self.addressing((operand, 2), c);
+        quote!(::libnewton::arrays::Array3(#a, #b, #c))
+    }
+
+    /// Build the instruction
+    fn instruction(&self, instruction: InstructionSet) -> TokenStream {
+        let name = quote!(::libnewton::instruction::InstructionSet);
+        match instruction {
+            InstructionSet::NOP => quote!(#name::NOP),
+            InstructionSet::BEGIN => quote!(#name::BEGIN),
+            InstructionSet::RUN => quote!(#name::RUN),
+            InstructionSet::TRANSMIT => quote!(#name::TRANSMIT),
+            InstructionSet::AIDX => quote!(#name::AIDX),
+            InstructionSet::RIDX => quote!(#name::RIDX),
+            InstructionSet::HOLD => quote!(#name::HOLD),
+            InstructionSet::NHOLD => quote!(#name::NHOLD),
+            InstructionSet::UPDATE => quote!(#name::UPDATE),
+            InstructionSet::PAUSE => quote!(#name::PAUSE),
+            InstructionSet::RESET => quote!(#name::RESET),
+            InstructionSet::HALT(a) => {
+                let a = self.addressing((0, 0), a);
+                quote!(#name::HALT(#a))
+            }
+            InstructionSet::RET(a) => {
+                let a = self.addressing((0, 0), a);
+                quote!(#name::RET(#a))
+            }
+            InstructionSet::JMP(target) => quote!(#name::JMP(#target)),
+            InstructionSet::BEQ(a, b, target) => {
+                let a = self.addressing((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::BEQ(#a, #b, #target))
+            }
+            InstructionSet::BNE(a, b, target) => {
+                let a = self.addressing((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::BNE(#a, #b, #target))
+            }
+            InstructionSet::LOAD(a, b) => {
+                let a = self.register((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::LOAD(#a, #b))
+            }
+            InstructionSet::ADD(a, b) => {
+                let a = self.register((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::ADD(#a, #b))
+            }
+            InstructionSet::SUB(a, b) => {
+                let a = self.register((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::SUB(#a, #b))
+            }
+            InstructionSet::FILL(a, b) => {
+                let a = self.array2(0, a);
+                let b = self.array3(1, b);
+                quote!(#name::FILL(#a, #b))
+            }
+            InstructionSet::HFILL(a, b) => {
+                let a = self.array2(0, a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::HFILL(#a, #b))
+            }
+            InstructionSet::SFILL(a, b) => {
+                let a = self.array2(0, a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::SFILL(#a, #b))
+            }
+            InstructionSet::LFILL(a, b) => {
+                let a = self.array2(0, a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::LFILL(#a, #b))
+            }
+            InstructionSet::PAINT(a, b) => {
+                let a = self.addressing((0, 0), a);
+                let b = self.array3(1, b);
+                quote!(#name::PAINT(#a, #b))
+            }
+            InstructionSet::HPAINT(a, b) => {
+                let a = self.addressing((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::HPAINT(#a, #b))
+            }
+            InstructionSet::SPAINT(a, b) => {
+                let a = self.addressing((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::SPAINT(#a, #b))
+            }
+            InstructionSet::LPAINT(a, b) => {
+                let a = self.addressing((0, 0), a);
+                let b = self.addressing((1, 0), b);
+                quote!(#name::LPAINT(#a, #b))
+            }
+            InstructionSet::EFFECT(code, a, b) => {
+                let code = match code {
+                    EffectCode::DIM => quote!(::libnewton::codes::EffectCode::DIM),
+                    EffectCode::BLEND => quote!(::libnewton::codes::EffectCode::BLEND),
+                    EffectCode::Custom(n) => quote!(::libnewton::codes::EffectCode::Custom(#n)),
+                };
+                let a = self.array2(1, a);
+                let b = self.addressing((2, 0), b);
+                quote!(#name::EFFECT(#code, #a, #b))
+            }
+            InstructionSet::DELAY(code, a) => {
+                let code = match code {
+                    DelayCode::MS => quote!(::libnewton::codes::DelayCode::MS),
+                    DelayCode::SEC => quote!(::libnewton::codes::DelayCode::SEC),
+                    DelayCode::MIN => quote!(::libnewton::codes::DelayCode::MIN),
+                    DelayCode::HRS => quote!(::libnewton::codes::DelayCode::HRS),
+                };
+                let a = self.addressing((1, 0), a);
+                quote!(#name::DELAY(#code, #a))
+            }
+            InstructionSet::GET(a) => {
+                let a = self.register((0, 0), a);
+                quote!(#name::GET(#a))
+            }
+        }
+    }
+}
+
+/// Assemble Prism Assembly Language at compile time into a `Vec<InstructionSet>`\
+/// Instructions are separated by `;` or new lines and labels are defined with `name:`\
+/// Immediates and registers can be replaced by a Rust expression written as `{expr}`,
+/// it must be a `u8` or a `Register` (only a `Register` where PAL only accepts registers)\
+/// Beware: interpolated registers inside an array must not be mixed with immediates,
+/// this can only be checked when the instruction is encoded
+///
+/// ```
+/// use libnewton::assembler;
+/// use libnewton_macros::prism;
+///
+/// let hue = 120;
+/// let script = prism! {
+///     BEGIN
+///     loop: FILL [0, 100] [{hue}, 255, 128]
+///     DELAY MS 200
+///     JMP loop
+///     RUN
+/// };
+///
+/// let source = "BEGIN; loop: FILL [0, 100] [120, 255, 128]; DELAY MS 200; JMP loop; RUN";
+/// assert_eq!(script, assembler::parse(source).unwrap());
+/// ```
+#[proc_macro]
+pub fn prism(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input.into(), false).into()
+}
+
+/// Same as [prism!] but the result is assembled into Prism Binary Format as a `&'static [u8]`\
+/// Expressions cannot be interpolated as the bytes are computed at compile time
+///
+/// ```
+/// use libnewton_macros::prism_bytes;
+///
+/// const SCRIPT: &[u8] = prism_bytes! { BEGIN; FILL [0, 100] [0, 255, 128]; RUN };
+/// assert_eq!(SCRIPT, [0x04, 0x4c, 0, 100, 0, 255, 128, 0x08]);
+/// ```
+#[proc_macro]
+pub fn prism_bytes(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    expand(input.into(), true).into()
+}
+
+/// Expand either [prism!] or [prism_bytes!]
+fn expand(input: TokenStream, bytes: bool) -> TokenStream {
+    let source = match Source::new(input) {
+        Ok(source) => source,
+        Err(error) => return error,
+    };
+
+    let instructions = match source.parse() {
+        Ok(instructions) => instructions,
+        Err(error) => return error,
+    };
+
+    if bytes {
+        if let Some(interpolation) = source.interpolations.first() {
+            return error(
+                interpolation.span,
+                "prism_bytes! cannot interpolate expressions, use prism! instead",
+            );
+        }
+
+        let (positions, instructions): (Vec<_>, Vec<_>) = instructions.into_iter().unzip();
+        return match binary::assemble(&instructions) {
+            Ok(bytes) => quote!({
+                let bytes: &'static [u8] = &[#(#bytes),*];
+                bytes
+            }),
+            Err(e) => {
+                let span = match e.location() {
+                    Some(Location::Index(index)) => source.span(positions[index]),
+                    _ => Span::call_site(),
+                };
+                error(span, &e.kind().to_string())
+            }
+        };
+    }
+
+    let instructions = instructions
+        .into_iter()
+        .enumerate()
+        .map(|(index, (_, instruction))| {
+            let interpolations = source
+                .interpolations
+                .iter()
+                .filter(|interpolation| interpolation.instruction == index)
+                .map(|interpolation| (interpolation.slot, &interpolation.expression))
+                .collect();
+            Operands { interpolations }.instruction(instruction)
+        });
+
+    quote!(::std::vec![#(#instructions),*])
+}
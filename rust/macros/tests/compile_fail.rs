@@ -0,0 +1,7 @@
+//! Assembler errors must be reported at the macro call site, pointing at the offending tokens
+
+#[test]
+fn compile_fail() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/*.rs");
+}
@@ -0,0 +1,114 @@
+//! Macros must expand to the same instructions (and bytes) as the assembler at runtime
+
+use libnewton::addressing::AddressingMode::{Immediate, Indirect};
+use libnewton::arrays::{Array2, Array3};
+use libnewton::assembler;
+use libnewton::binary;
+use libnewton::error::Error;
+use libnewton::instruction::InstructionSet;
+use libnewton::registers::Register;
+use libnewton_macros::{prism, prism_bytes};
+
+#[test]
+fn prism_matches_the_assembler() {
+    let script = prism! {
+        BEGIN
+        loop: fill [0, 100] [120, 255, 0x80]
+        EFFECT DIM [$0, $1] 30; EFFECT 9 [0, 50] 0b11
+        BEQ $SF, 0, loop
+        DELAY SEC $RV
+        HALT 7; RET $0
+        JMP loop
+        RUN
+    };
+
+    let source = "
+        BEGIN
+        loop: FILL [0, 100] [120, 255, 128]
+        EFFECT DIM [$0, $1] 30; EFFECT 9 [0, 50] 3
+        BEQ $SF 0 loop
+        DELAY SEC $RV
+        HALT 7; RET $0
+        JMP loop
+        RUN";
+    assert_eq!(script, assembler::parse(source).unwrap());
+}
+
+#[test]
+fn interpolated_immediates() {
+    let hue: u8 = 120;
+    let script = prism! {
+        HPAINT {hue} {hue + 1}
+        FILL [0, {hue}] [{hue}, 255, 128]
+        LOAD $0 {hue}
+    };
+
+    assert_eq!(
+        script,
+        [
+            InstructionSet::HPAINT(Immediate(120), Immediate(121)),
+            InstructionSet::FILL(
+                Array2(Immediate(0), Immediate(120)),
+                Array3(Immediate(120), Immediate(255), Immediate(128))
+            ),
+            InstructionSet::LOAD(Register::general(0).unwrap(), Immediate(120)),
+        ]
+    );
+}
+
+#[test]
+fn interpolated_registers() {
+    let r = Register::general(3).unwrap();
+    let script = prism! {
+        HPAINT {r} {Register::RV}
+        FILL [{r}, $1] [$2, {r}, $4]
+        ADD {r} {r}
+        GET {r}
+    };
+
+    let n = |n| Register::general(n).unwrap();
+    assert_eq!(
+        script,
+        [
+            InstructionSet::HPAINT(Indirect(r), Indirect(Register::RV)),
+            InstructionSet::FILL(
+                Array2(Indirect(r), Indirect(n(1))),
+                Array3(Indirect(n(2)), Indirect(r), Indirect(n(4)))
+            ),
+            InstructionSet::ADD(r, Indirect(r)),
+            InstructionSet::GET(r),
+        ]
+    );
+}
+
+#[test]
+fn interpolated_arrays_are_checked_when_encoded() {
+    let r = Register::general(3).unwrap();
+    let script = prism! { FILL [{r}, 1] [1, 2, 3] };
+
+    assert_eq!(
+        script[0],
+        InstructionSet::FILL(
+            Array2(Indirect(r), Immediate(1)),
+            Array3(Immediate(1), Immediate(2), Immediate(3))
+        )
+    );
+    assert_eq!(script[0].encode(), Err(Error::MixedAddressing));
+}
+
+#[test]
+fn prism_bytes_matches_the_assembler() {
+    const SCRIPT: &[u8] = prism_bytes! {
+        BEGIN
+        loop: LPAINT 0 $RV
+        DELAY MS 200
+        JMP loop
+        RUN
+    };
+
+    assert_eq!(SCRIPT, [0x04, 0x69, 0, 5, 0x70, 0, 200, 0x28, 0, 0x08]);
+    assert_eq!(
+        SCRIPT,
+        binary::assemble_str("BEGIN; loop: LPAINT 0 $RV; DELAY MS 200; JMP loop; RUN").unwrap()
+    );
+}
@@ -0,0 +1,9 @@
+use libnewton_macros::prism;
+
+fn main() {
+    let _ = prism! {
+        BEGIN
+        FLIL [0, 100] [120, 255, 128]
+        RUN
+    };
+}
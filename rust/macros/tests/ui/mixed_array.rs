@@ -0,0 +1,7 @@
+use libnewton_macros::prism_bytes;
+
+const BYTES: &[u8] = prism_bytes! { FILL [0, 100] [120, $1, 128] };
+
+fn main() {
+    let _ = BYTES;
+}
@@ -1,10 +1,43 @@
+use core::fmt;
+
 use crate::registers::Register;
 
 /// Use an immediate value or use one stored in memory for values
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum AddressingMode {
     /// Use the explicitly provided value
     Immediate(u8),
     /// Use the value stored in the register at this memory address
     Indirect(Register),
-}
\ No newline at end of file
+}
+
+impl AddressingMode {
+    /// Whether the value must be read from a register
+    pub fn is_indirect(&self) -> bool {
+        matches!(self, AddressingMode::Indirect(_))
+    }
+}
+
+impl From<u8> for AddressingMode {
+    /// Use the value as an immediate
+    fn from(value: u8) -> Self {
+        AddressingMode::Immediate(value)
+    }
+}
+
+impl From<Register> for AddressingMode {
+    /// Use the value stored in the register
+    fn from(register: Register) -> Self {
+        AddressingMode::Indirect(register)
+    }
+}
+
+impl fmt::Display for AddressingMode {
+    /// Prism Assembly Language representation, a number or a register (e.g. `120`, `$SC`, `$2`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressingMode::Immediate(value) => write!(f, "{}", value),
+            AddressingMode::Indirect(register) => write!(f, "{}", register),
+        }
+    }
+}
@@ -1,7 +1,12 @@
+use core::fmt;
+use core::ops::Range;
+
 use crate::addressing::AddressingMode;
+#[cfg(feature = "std")]
+use crate::error::Error;
 
 /// Enum that represents all the array types
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Array {
     /// Ranges are selected using an Array2
     Range(Array2<AddressingMode>),
@@ -10,55 +15,117 @@ pub enum Array {
 }
 
 /// Prism Assembly Language representation of an array with two elements (range selection)
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Array2<T>(pub T, pub T);
 
+#[cfg(feature = "std")]
 impl<T> TryFrom<&Vec<T>> for Array2<T>
 where
     T: Clone,
 {
-    type Error = ();
+    type Error = Error;
 
     // Copy the contents of a vector into an Array
     fn try_from(vec: &Vec<T>) -> Result<Self, Self::Error> {
         if vec.len() == 2 {
             Ok(Self(vec[0].clone(), vec[1].clone()))
         } else {
-            Err(())
+            Err(Error::ArrayLength {
+                expected: 2,
+                found: vec.len(),
+            })
         }
     }
 }
 
-impl<T> Into<Vec<T>> for Array2<T> {
+#[cfg(feature = "std")]
+impl<T> From<Array2<T>> for Vec<T> {
     /// Transform an [Array2] back into a [Vec] or size 2
-    fn into(self) -> Vec<T> {
-        vec![self.0, self.1]
+    fn from(array: Array2<T>) -> Self {
+        vec![array.0, array.1]
+    }
+}
+
+impl<T> From<Array2<T>> for [T; 2] {
+    /// Transform an [Array2] into a fixed size array
+    fn from(array: Array2<T>) -> Self {
+        [array.0, array.1]
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Array2<T> {
+    /// Prism Assembly Language representation (e.g. `[0, 100]`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}]", self.0, self.1)
+    }
+}
+
+impl From<Range<u8>> for Array2<AddressingMode> {
+    /// Select a range of LEDs using immediate values, the end is exclusive (like $R1)
+    fn from(range: Range<u8>) -> Self {
+        Array2(
+            AddressingMode::Immediate(range.start),
+            AddressingMode::Immediate(range.end),
+        )
+    }
+}
+
+impl Array2<AddressingMode> {
+    /// Whether both elements share the same addressing mode (required for encoding)
+    pub fn is_uniform(&self) -> bool {
+        self.0.is_indirect() == self.1.is_indirect()
     }
 }
 
 /// Prism Assembly Language representation of an array with HSL components
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Array3<T>(pub T, pub T, pub T);
 
+#[cfg(feature = "std")]
 impl<T> TryFrom<&Vec<T>> for Array3<T>
 where
     T: Clone,
 {
-    type Error = ();
+    type Error = Error;
 
     // Copy the contents of a vector into an Array
     fn try_from(vec: &Vec<T>) -> Result<Self, Self::Error> {
         if vec.len() == 3 {
             Ok(Self(vec[0].clone(), vec[1].clone(), vec[2].clone()))
         } else {
-            Err(())
+            Err(Error::ArrayLength {
+                expected: 3,
+                found: vec.len(),
+            })
         }
     }
 }
 
-impl<T> Into<Vec<T>> for Array3<T> {
+#[cfg(feature = "std")]
+impl<T> From<Array3<T>> for Vec<T> {
     /// Transform an [Array3] back into a [Vec] or size 3
-    fn into(self) -> Vec<T> {
-        vec![self.0, self.1, self.2]
+    fn from(array: Array3<T>) -> Self {
+        vec![array.0, array.1, array.2]
+    }
+}
+
+impl<T> From<Array3<T>> for [T; 3] {
+    /// Transform an [Array3] into a fixed size array
+    fn from(array: Array3<T>) -> Self {
+        [array.0, array.1, array.2]
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Array3<T> {
+    /// Prism Assembly Language representation (e.g. `[$0, $1, $2]`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}, {}, {}]", self.0, self.1, self.2)
+    }
+}
+
+impl Array3<AddressingMode> {
+    /// Whether every element shares the same addressing mode (required for encoding)
+    pub fn is_uniform(&self) -> bool {
+        self.0.is_indirect() == self.1.is_indirect() && self.1.is_indirect() == self.2.is_indirect()
     }
 }
@@ -0,0 +1,792 @@
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use crate::addressing::AddressingMode;
+use crate::arrays::{Array2, Array3};
+use crate::codes::{DelayCode, EffectCode};
+use crate::error::{Error, Location, Position};
+use crate::instruction::InstructionSet;
+use crate::labels::{self, Item};
+use crate::proto;
+use crate::registers::Register;
+
+/// Lexical units of Prism Assembly Language
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// Instruction mnemonics and code names (FILL, MS, ...)
+    Identifier(String),
+    /// Register reference ($SC, $0, ...)
+    Register(Register),
+    /// Numeric literal in decimal, hexadecimal (0x) or binary (0b)
+    Number(u32),
+    /// Opening of an array `[`
+    LeftBracket,
+    /// Closing of an array `]`
+    RightBracket,
+    /// Separator between array elements (optional between operands) `,`
+    Comma,
+    /// Marks the end of a label definition `:`
+    Colon,
+    /// End of an instruction, either a new line or `;`
+    Separator,
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "'{}'", name),
+            Token::Register(register) => write!(f, "register {}", register),
+            Token::Number(number) => write!(f, "number {}", number),
+            Token::LeftBracket => write!(f, "'['"),
+            Token::RightBracket => write!(f, "']'"),
+            Token::Comma => write!(f, "','"),
+            Token::Colon => write!(f, "':'"),
+            Token::Separator => write!(f, "end of instruction"),
+        }
+    }
+}
+
+/// Transform source code into a stream of [Token]
+struct Lexer<'a> {
+    chars: Peekable<Chars<'a>>,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            chars: source.chars().peekable(),
+            line: 1,
+            column: 1,
+        }
+    }
+
+    /// Current position of the lexer in the source code
+    fn position(&self) -> Position {
+        Position {
+            line: self.line,
+            column: self.column,
+        }
+    }
+
+    /// Consume the next character keeping track of the position
+    fn bump(&mut self) -> Option<char> {
+        let c = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    /// Consume every character that satisfies the predicate
+    fn take_while(&mut self, predicate: impl Fn(char) -> bool) -> String {
+        let mut result = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if !predicate(c) {
+                break;
+            }
+            result.push(c);
+            self.bump();
+        }
+        result
+    }
+
+    /// Consume the next token starting at character [c]\
+    /// returns [None] for whitespace and comments
+    fn token(&mut self, c: char, position: Position) -> Result<Option<Token>, Error> {
+        let token = match c {
+            // Line breaks and semicolons end an instruction
+            '\n' | ';' => {
+                self.bump();
+                Token::Separator
+            }
+            // Ignore any other whitespace
+            c if c.is_whitespace() => {
+                self.bump();
+                return Ok(None);
+            }
+            // Comments last until the end of the line
+            '/' => {
+                self.bump();
+                if self.bump() != Some('/') {
+                    return Err(error(position, "unexpected character '/'"));
+                }
+                self.take_while(|c| c != '\n');
+                return Ok(None);
+            }
+            '[' => {
+                self.bump();
+                Token::LeftBracket
+            }
+            ']' => {
+                self.bump();
+                Token::RightBracket
+            }
+            ',' => {
+                self.bump();
+                Token::Comma
+            }
+            ':' => {
+                self.bump();
+                Token::Colon
+            }
+            '$' => {
+                self.bump();
+                let name = self.take_while(|c| c.is_ascii_alphanumeric());
+                Token::Register(
+                    parse_register(&name).map_err(|e| e.at(Location::Source(position)))?,
+                )
+            }
+            c if c.is_ascii_digit() => {
+                let literal = self.take_while(is_word);
+                Token::Number(
+                    parse_number(&literal)
+                        .ok_or_else(|| error(position, format!("invalid number '{}'", literal)))?,
+                )
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => Token::Identifier(self.take_while(is_word)),
+            c => return Err(error(position, format!("unexpected character '{}'", c))),
+        };
+
+        Ok(Some(token))
+    }
+
+    /// Consume the whole source code and return the tokens with their position\
+    /// alongside the position of the end of the source
+    fn tokenize(mut self) -> Result<(Vec<(Position, Token)>, Position), Error> {
+        let mut tokens = vec![];
+
+        while let Some(&c) = self.chars.peek() {
+            let position = self.position();
+            if let Some(token) = self.token(c, position)? {
+                tokens.push((position, token));
+            }
+        }
+
+        Ok((tokens, self.position()))
+    }
+}
+
+/// Characters allowed inside identifiers and numeric literals
+fn is_word(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Parse the name of a register without the `$` prefix
+fn parse_register(name: &str) -> Result<Register, Error> {
+    let unknown = || Error::Syntax(format!("unknown register '${}'", name));
+
+    // General purpose registers are only numbers
+    if !name.is_empty() && name.chars().all(|c| c.is_ascii_digit()) {
+        return Register::general(name.parse().map_err(|_| unknown())?);
+    }
+
+    match proto::Registers::from_str_name(&name.to_ascii_uppercase()).ok_or_else(unknown)? {
+        proto::Registers::Sc => Ok(Register::SC),
+        proto::Registers::Sf => Ok(Register::SF),
+        proto::Registers::Pc => Ok(Register::PC),
+        proto::Registers::Pp => Ok(Register::PP),
+        proto::Registers::Rv => Ok(Register::RV),
+        proto::Registers::R0 => Ok(Register::R0),
+        proto::Registers::R1 => Ok(Register::R1),
+        proto::Registers::Po => Ok(Register::PO),
+        proto::Registers::General => Err(unknown()),
+    }
+}
+
+/// Parse a numeric literal in decimal, hexadecimal (0x) or binary (0b)
+fn parse_number(literal: &str) -> Option<u32> {
+    let literal = literal.replace('_', "");
+    if let Some(hex) = literal
+        .strip_prefix("0x")
+        .or_else(|| literal.strip_prefix("0X"))
+    {
+        u32::from_str_radix(hex, 16).ok()
+    } else if let Some(bin) = literal
+        .strip_prefix("0b")
+        .or_else(|| literal.strip_prefix("0B"))
+    {
+        u32::from_str_radix(bin, 2).ok()
+    } else {
+        literal.parse().ok()
+    }
+}
+
+/// Build a syntax [Error] at the given position
+fn error(position: Position, message: impl Into<String>) -> Error {
+    Error::Syntax(message.into()).at(Location::Source(position))
+}
+
+/// Transform a stream of [Token] into instructions
+struct Parser {
+    tokens: Vec<(Position, Token)>,
+    index: usize,
+    /// Position right after the last token, used for errors at the end of the source
+    end: Position,
+}
+
+impl Parser {
+    /// Peek the next token without consuming it
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    /// Position of the next token
+    fn position(&self) -> Position {
+        self.tokens
+            .get(self.index)
+            .map(|&(position, _)| position)
+            .unwrap_or(self.end)
+    }
+
+    /// Consume the next token
+    fn next(&mut self) -> Option<(Position, Token)> {
+        let token = self.tokens.get(self.index).cloned();
+        self.index += 1;
+        token
+    }
+
+    /// Build an error describing the unexpected token found instead of [expected]
+    fn unexpected(&self, expected: &str) -> Error {
+        match self.peek() {
+            Some(token) => error(
+                self.position(),
+                format!("expected {}, found {}", expected, token),
+            ),
+            None => error(
+                self.position(),
+                format!("expected {}, found end of file", expected),
+            ),
+        }
+    }
+
+    /// Consume the next token only if it is equal to [token]
+    fn accept(&mut self, token: &Token) -> bool {
+        if self.peek() == Some(token) {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Consume the next token and fail if it is not equal to [token]
+    fn expect(&mut self, token: &Token, expected: &str) -> Result<(), Error> {
+        if self.accept(token) {
+            Ok(())
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
+
+    /// Commas between operands are optional
+    fn operand_separator(&mut self) {
+        self.accept(&Token::Comma);
+    }
+
+    /// Parse a numeric literal that must fit in a byte
+    fn byte(&mut self) -> Result<u8, Error> {
+        match self.peek() {
+            Some(&Token::Number(number)) => {
+                let position = self.position();
+                self.index += 1;
+                u8::try_from(number).map_err(|_| {
+                    error(
+                        position,
+                        format!("value {} does not fit in a byte (0-255)", number),
+                    )
+                })
+            }
+            _ => Err(self.unexpected("a number")),
+        }
+    }
+
+    /// Parse a register reference
+    fn register(&mut self) -> Result<Register, Error> {
+        match self.peek() {
+            Some(&Token::Register(register)) => {
+                self.index += 1;
+                Ok(register)
+            }
+            _ => Err(self.unexpected("a register")),
+        }
+    }
+
+    /// Parse either an immediate value or a register reference
+    fn addressing(&mut self) -> Result<AddressingMode, Error> {
+        match self.peek() {
+            Some(Token::Number(_)) => Ok(AddressingMode::Immediate(self.byte()?)),
+            Some(Token::Register(_)) => Ok(AddressingMode::Indirect(self.register()?)),
+            _ => Err(self.unexpected("a number or a register")),
+        }
+    }
+
+    /// Parse an array of [N] elements separated by commas
+    fn array<const N: usize>(&mut self) -> Result<Vec<AddressingMode>, Error> {
+        self.expect(&Token::LeftBracket, "'['")?;
+        let mut elements = vec![];
+        for i in 0..N {
+            if i > 0 {
+                self.expect(&Token::Comma, "','")?;
+            }
+            elements.push(self.addressing()?);
+        }
+        self.expect(&Token::RightBracket, "']'")?;
+        Ok(elements)
+    }
+
+    /// Parse an array with two elements `[a, b]`
+    fn array2(&mut self) -> Result<Array2<AddressingMode>, Error> {
+        let position = self.position();
+        let elements = self.array::<2>()?;
+        let array = Array2(elements[0], elements[1]);
+
+        if !array.is_uniform() {
+            return Err(Error::MixedAddressing.at(Location::Source(position)));
+        }
+
+        Ok(array)
+    }
+
+    /// Parse an array with three elements `[h, s, l]`
+    fn array3(&mut self) -> Result<Array3<AddressingMode>, Error> {
+        let position = self.position();
+        let elements = self.array::<3>()?;
+        let array = Array3(elements[0], elements[1], elements[2]);
+
+        if !array.is_uniform() {
+            return Err(Error::MixedAddressing.at(Location::Source(position)));
+        }
+
+        Ok(array)
+    }
+
+    /// Parse the target of a branching instruction, either an absolute position or a label\
+    /// labels are stored in [label] and resolved after every instruction is parsed
+    fn target(&mut self, label: &mut Option<String>) -> Result<u8, Error> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                *label = Some(name.clone());
+                self.index += 1;
+                Ok(0)
+            }
+            Some(Token::Number(_)) => self.byte(),
+            _ => Err(self.unexpected("a position or a label")),
+        }
+    }
+
+    /// Parse the code of an effect, either its name or a number for custom effects
+    fn effect_code(&mut self) -> Result<EffectCode, Error> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let position = self.position();
+                let code = name
+                    .parse()
+                    .map_err(|e: Error| e.at(Location::Source(position)))?;
+                self.index += 1;
+                Ok(code)
+            }
+            Some(Token::Number(_)) => Ok(EffectCode::from(self.byte()?)),
+            _ => Err(self.unexpected("an effect (DIM, BLEND or a number)")),
+        }
+    }
+
+    /// Parse the name of a delay time unit
+    fn delay_code(&mut self) -> Result<DelayCode, Error> {
+        match self.peek() {
+            Some(Token::Identifier(name)) => {
+                let code = proto::DelayCode::from_str_name(&name.to_ascii_uppercase()).ok_or_else(
+                    || error(self.position(), format!("unknown delay code '{}'", name)),
+                )?;
+                self.index += 1;
+                Ok(code.into())
+            }
+            _ => Err(self.unexpected("a delay code (MS, SEC, MIN or HRS)")),
+        }
+    }
+
+    /// Parse a single instruction with its operands
+    fn instruction(&mut self) -> Result<Item, Error> {
+        let (position, token) = self.next().expect("instruction called without tokens");
+        let mnemonic = match token {
+            Token::Identifier(mnemonic) => mnemonic,
+            token => {
+                return Err(error(
+                    position,
+                    format!("expected an instruction, found {}", token),
+                ))
+            }
+        };
+
+        let instruction = proto::InstructionSet::from_str_name(&mnemonic.to_ascii_uppercase())
+            .ok_or_else(|| error(position, format!("unknown instruction '{}'", mnemonic)))?;
+
+        // Label referenced by a branching instruction
+        let mut label = None;
+
+        let instruction = match instruction {
+            proto::InstructionSet::Nop => InstructionSet::NOP,
+            proto::InstructionSet::Begin => InstructionSet::BEGIN,
+            proto::InstructionSet::Run => InstructionSet::RUN,
+            proto::InstructionSet::Transmit => InstructionSet::TRANSMIT,
+            proto::InstructionSet::Halt => InstructionSet::HALT(self.addressing()?),
+            proto::InstructionSet::Aidx => InstructionSet::AIDX,
+            proto::InstructionSet::Ridx => InstructionSet::RIDX,
+            proto::InstructionSet::Hold => InstructionSet::HOLD,
+            proto::InstructionSet::Nhold => InstructionSet::NHOLD,
+            proto::InstructionSet::Update => InstructionSet::UPDATE,
+            proto::InstructionSet::Jmp => InstructionSet::JMP(self.target(&mut label)?),
+            proto::InstructionSet::Ret => InstructionSet::RET(self.addressing()?),
+            proto::InstructionSet::Beq | proto::InstructionSet::Bne => {
+                let a = self.addressing()?;
+                self.operand_separator();
+                let b = self.addressing()?;
+                self.operand_separator();
+                let c = self.target(&mut label)?;
+                match instruction {
+                    proto::InstructionSet::Beq => InstructionSet::BEQ(a, b, c),
+                    _ => InstructionSet::BNE(a, b, c),
+                }
+            }
+            proto::InstructionSet::Load
+            | proto::InstructionSet::Add
+            | proto::InstructionSet::Sub => {
+                let register = self.register()?;
+                self.operand_separator();
+                let value = self.addressing()?;
+                match instruction {
+                    proto::InstructionSet::Load => InstructionSet::LOAD(register, value),
+                    proto::InstructionSet::Add => InstructionSet::ADD(register, value),
+                    _ => InstructionSet::SUB(register, value),
+                }
+            }
+            proto::InstructionSet::Fill => {
+                let range = self.array2()?;
+                self.operand_separator();
+                InstructionSet::FILL(range, self.array3()?)
+            }
+            proto::InstructionSet::Hfill
+            | proto::InstructionSet::Sfill
+            | proto::InstructionSet::Lfill => {
+                let range = self.array2()?;
+                self.operand_separator();
+                let value = self.addressing()?;
+                match instruction {
+                    proto::InstructionSet::Hfill => InstructionSet::HFILL(range, value),
+                    proto::InstructionSet::Sfill => InstructionSet::SFILL(range, value),
+                    _ => InstructionSet::LFILL(range, value),
+                }
+            }
+            proto::InstructionSet::Paint => {
+                let index = self.addressing()?;
+                self.operand_separator();
+                InstructionSet::PAINT(index, self.array3()?)
+            }
+            proto::InstructionSet::Hpaint
+            | proto::InstructionSet::Spaint
+            | proto::InstructionSet::Lpaint => {
+                let index = self.addressing()?;
+                self.operand_separator();
+                let value = self.addressing()?;
+                match instruction {
+                    proto::InstructionSet::Hpaint => InstructionSet::HPAINT(index, value),
+                    proto::InstructionSet::Spaint => InstructionSet::SPAINT(index, value),
+                    _ => InstructionSet::LPAINT(index, value),
+                }
+            }
+            proto::InstructionSet::Effect => {
+                let code = self.effect_code()?;
+                self.operand_separator();
+                let range = self.array2()?;
+                self.operand_separator();
+                InstructionSet::EFFECT(code, range, self.addressing()?)
+            }
+            proto::InstructionSet::Delay => {
+                let code = self.delay_code()?;
+                self.operand_separator();
+                InstructionSet::DELAY(code, self.addressing()?)
+            }
+            proto::InstructionSet::Pause => InstructionSet::PAUSE,
+            proto::InstructionSet::Get => InstructionSet::GET(self.register()?),
+            proto::InstructionSet::Reset => InstructionSet::RESET,
+        };
+
+        Ok(match label {
+            Some(label) => Item::Branch(instruction, label),
+            None => Item::Instruction(instruction),
+        })
+    }
+
+    /// Parse every instruction until the end of the token stream and resolve the labels\
+    /// every instruction is paired with the position where it starts
+    fn parse(mut self) -> Result<Vec<(Position, InstructionSet)>, Error> {
+        let mut items = vec![];
+        let mut positions = vec![];
+
+        while self.peek().is_some() {
+            // Skip empty lines
+            if self.accept(&Token::Separator) {
+                continue;
+            }
+
+            // Label definitions can be followed by an instruction in the same line
+            if let (Some(Token::Identifier(name)), Some((_, Token::Colon))) =
+                (self.peek(), self.tokens.get(self.index + 1))
+            {
+                positions.push(self.position());
+                items.push(Item::Label(name.clone()));
+                self.index += 2;
+                continue;
+            }
+
+            positions.push(self.position());
+            items.push(self.instruction()?);
+
+            // Every instruction must be followed by a separator or the end of the source
+            if self.peek().is_some() && !self.accept(&Token::Separator) {
+                return Err(self.unexpected("end of instruction"));
+            }
+        }
+
+        // Locate label errors in the source code
+        let instructions = labels::resolve(&items).map_err(|e| match e.location() {
+            Some(Location::Index(index)) => e.kind().clone().at(Location::Source(positions[index])),
+            _ => e,
+        })?;
+
+        // Labels are removed when resolving, the remaining items keep their order
+        let positions = items
+            .iter()
+            .zip(positions)
+            .filter(|(item, _)| !matches!(item, Item::Label(_)))
+            .map(|(_, position)| position);
+
+        Ok(positions.zip(instructions).collect())
+    }
+}
+
+/// Parse Prism Assembly Language source code into a set of instructions\
+/// Instructions are separated by new lines or `;`, and comments start with `//`\
+/// Labels are defined with `name:` and can be used as the target of JMP, BEQ and BNE
+pub fn parse(source: &str) -> Result<Vec<InstructionSet>, Error> {
+    Ok(parse_located(source)?
+        .into_iter()
+        .map(|(_, instruction)| instruction)
+        .collect())
+}
+
+/// Same as [parse] but every instruction is paired with the [Position] where it starts
+pub fn parse_located(source: &str) -> Result<Vec<(Position, InstructionSet)>, Error> {
+    let (tokens, end) = Lexer::new(source).tokenize()?;
+    Parser {
+        tokens,
+        index: 0,
+        end,
+    }
+    .parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addressing::AddressingMode::{Immediate, Indirect};
+
+    fn at(line: usize, column: usize) -> Option<Location> {
+        Some(Location::Source(Position { line, column }))
+    }
+
+    /// Location and message of the error found in [source]
+    fn failure(source: &str) -> (Option<Location>, String) {
+        let error = parse(source).unwrap_err();
+        (error.location(), error.kind().to_string())
+    }
+
+    fn general(n: u8) -> Register {
+        Register::general(n).unwrap()
+    }
+
+    #[test]
+    fn comments_are_ignored() {
+        assert_eq!(
+            parse("// header\nNOP // trailing comment; RUN\n    // indented\nRUN//"),
+            Ok(vec![InstructionSet::NOP, InstructionSet::RUN])
+        );
+        assert_eq!(
+            failure("NOP / comment"),
+            (at(1, 5), String::from("unexpected character '/'"))
+        );
+    }
+
+    #[test]
+    fn numeric_literals() {
+        assert_eq!(
+            parse("LPAINT 0x1F 0b101; LPAINT 1_0 0XfF; LPAINT 0B1 007"),
+            Ok(vec![
+                InstructionSet::LPAINT(Immediate(31), Immediate(5)),
+                InstructionSet::LPAINT(Immediate(10), Immediate(255)),
+                InstructionSet::LPAINT(Immediate(1), Immediate(7)),
+            ])
+        );
+        assert_eq!(
+            failure("LPAINT 0x 1"),
+            (at(1, 8), String::from("invalid number '0x'"))
+        );
+        assert_eq!(
+            failure("LPAINT 0b12 1"),
+            (at(1, 8), String::from("invalid number '0b12'"))
+        );
+        assert_eq!(
+            failure("LPAINT 12ab 1"),
+            (at(1, 8), String::from("invalid number '12ab'"))
+        );
+    }
+
+    #[test]
+    fn names_are_case_insensitive() {
+        assert_eq!(
+            parse("fill [0, 100] [1, 2, 3]; Delay ms 5; effect Dim [0, 1] 2; lpaint $rv $Sc"),
+            parse("FILL [0, 100] [1, 2, 3]; DELAY MS 5; EFFECT DIM [0, 1] 2; LPAINT $RV $SC")
+        );
+    }
+
+    #[test]
+    fn separators() {
+        assert_eq!(
+            parse("NOP;NOP\nNOP\n\n;; NOP;\r\n"),
+            Ok(vec![InstructionSet::NOP; 4])
+        );
+        assert_eq!(
+            failure("NOP NOP"),
+            (
+                at(1, 5),
+                String::from("expected end of instruction, found 'NOP'")
+            )
+        );
+    }
+
+    #[test]
+    fn commas_between_operands_are_optional() {
+        assert_eq!(parse("BEQ $0, 1, 2"), parse("BEQ $0 1 2"));
+        assert_eq!(parse("LOAD $0, 5"), parse("LOAD $0 5"));
+        assert_eq!(
+            parse("EFFECT DIM, [0, 100], 5"),
+            parse("EFFECT DIM [0, 100] 5")
+        );
+        assert_eq!(
+            parse("FILL [$0, $1], [$2, $3, $4]"),
+            Ok(vec![InstructionSet::FILL(
+                Array2(Indirect(general(0)), Indirect(general(1))),
+                Array3(
+                    Indirect(general(2)),
+                    Indirect(general(3)),
+                    Indirect(general(4))
+                )
+            )])
+        );
+
+        // Not between array elements
+        assert_eq!(
+            failure("FILL [0 100] [1, 2, 3]"),
+            (at(1, 9), String::from("expected ',', found number 100"))
+        );
+    }
+
+    #[test]
+    fn byte_overflow() {
+        assert_eq!(
+            failure("LPAINT 256 0"),
+            (
+                at(1, 8),
+                String::from("value 256 does not fit in a byte (0-255)")
+            )
+        );
+        assert_eq!(
+            failure("NOP\nJMP 0x100"),
+            (
+                at(2, 5),
+                String::from("value 256 does not fit in a byte (0-255)")
+            )
+        );
+        assert_eq!(
+            failure("LPAINT 4294967296 0"),
+            (at(1, 8), String::from("invalid number '4294967296'"))
+        );
+    }
+
+    #[test]
+    fn unknown_names() {
+        assert_eq!(
+            failure("GET $XY"),
+            (at(1, 5), String::from("unknown register '$XY'"))
+        );
+        assert_eq!(
+            parse("GET $32").unwrap_err(),
+            Error::RegisterOutOfRange(32).at(Location::Source(Position { line: 1, column: 5 }))
+        );
+        assert_eq!(
+            failure("NOP\n  FLIL [0, 1] [1, 2, 3]"),
+            (at(2, 3), String::from("unknown instruction 'FLIL'"))
+        );
+        assert_eq!(
+            failure("DELAY YEARS 1"),
+            (at(1, 7), String::from("unknown delay code 'YEARS'"))
+        );
+        assert_eq!(
+            failure("[0, 1]"),
+            (at(1, 1), String::from("expected an instruction, found '['"))
+        );
+    }
+
+    #[test]
+    fn positions_of_instructions() {
+        assert_eq!(
+            parse_located("BEGIN\n  loop: NOP;  LPAINT 0 1\nJMP loop; RUN"),
+            Ok(vec![
+                (Position { line: 1, column: 1 }, InstructionSet::BEGIN),
+                (Position { line: 2, column: 9 }, InstructionSet::NOP),
+                (
+                    Position {
+                        line: 2,
+                        column: 15
+                    },
+                    InstructionSet::LPAINT(Immediate(0), Immediate(1))
+                ),
+                (Position { line: 3, column: 1 }, InstructionSet::JMP(0)),
+                (
+                    Position {
+                        line: 3,
+                        column: 11
+                    },
+                    InstructionSet::RUN
+                ),
+            ])
+        );
+    }
+
+    #[test]
+    fn positions_of_errors() {
+        assert_eq!(
+            failure("NOP\n  FILL [0, $0] [1, 2, 3]"),
+            (
+                at(2, 8),
+                String::from("array elements must all be immediate values or all be registers")
+            )
+        );
+        assert_eq!(
+            failure("NOP\n\nJMP nowhere"),
+            (at(3, 1), String::from("undefined label 'nowhere'"))
+        );
+        assert_eq!(
+            failure("NOP\nLPAINT 0"),
+            (
+                at(2, 9),
+                String::from("expected a number or a register, found end of file")
+            )
+        );
+    }
+}
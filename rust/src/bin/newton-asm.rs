@@ -0,0 +1,177 @@
+//! Assemble Prism Assembly Language source code into Prism Binary Format
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::ExitCode;
+
+use libnewton::assembler;
+use libnewton::error::{Error, Location};
+
+const USAGE: &str = "\
+Usage: newton-asm [OPTIONS] <INPUT>
+
+Assemble Prism Assembly Language (PAL) into Prism Binary Format
+
+Arguments:
+  <INPUT>                  PAL source file, '-' reads from stdin
+
+Options:
+  -f, --format <FORMAT>    Output format: raw, hex or listing [default: raw]
+  -o, --output <FILE>      Output file, '-' writes to stdout [default: -]
+  -h, --help               Print this message";
+
+/// What to write once the source is assembled
+#[derive(Copy, Clone)]
+enum Format {
+    /// Prism Binary Format as is
+    Raw,
+    /// Bytes as hexadecimal pairs, 16 per line
+    Hex,
+    /// Byte offset, encoded bytes and source line of every instruction
+    Listing,
+}
+
+/// Command line arguments
+struct Arguments {
+    input: String,
+    output: String,
+    format: Format,
+}
+
+impl Arguments {
+    /// Parse the command line arguments, returns [None] if help was requested
+    fn parse() -> Result<Option<Self>, String> {
+        let mut input = None;
+        let mut output = String::from("-");
+        let mut format = Format::Raw;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-f" | "--format" => {
+                    format = match args.next().as_deref() {
+                        Some("raw") => Format::Raw,
+                        Some("hex") => Format::Hex,
+                        Some("listing") => Format::Listing,
+                        Some(other) => return Err(format!("unknown format '{}'", other)),
+                        None => return Err(format!("missing value for '{}'", arg)),
+                    }
+                }
+                "-o" | "--output" => {
+                    output = args
+                        .next()
+                        .ok_or_else(|| format!("missing value for '{}'", arg))?
+                }
+                _ if arg.starts_with('-') && arg != "-" => {
+                    return Err(format!("unknown option '{}'", arg))
+                }
+                _ if input.is_none() => input = Some(arg),
+                _ => return Err(format!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        Ok(Some(Self {
+            input: input.ok_or("missing input file")?,
+            output,
+            format,
+        }))
+    }
+}
+
+/// Write the bytes as hexadecimal pairs separated by spaces
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Assemble the [source] into the given [format]\
+/// errors are reported as `file:line:column: message`
+fn assemble(name: &str, source: &str, format: Format) -> Result<Vec<u8>, String> {
+    let report = |error: Error| match error.location() {
+        Some(Location::Source(position)) => format!("{}:{}: {}", name, position, error.kind()),
+        _ => format!("{}: {}", name, error),
+    };
+
+    let instructions = assembler::parse_located(source).map_err(report)?;
+
+    let mut bytes = vec![];
+    let mut listing = String::new();
+    let lines: Vec<&str> = source.lines().collect();
+
+    for (position, instruction) in instructions {
+        let encoded = instruction
+            .encode()
+            .map_err(|e| report(e.at(Location::Source(position))))?;
+
+        let line = lines.get(position.line - 1).map_or("", |line| line.trim());
+        writeln!(
+            listing,
+            "{:04x}  {:<17}  {:>4}  {}",
+            bytes.len(),
+            hex(&encoded),
+            position.line,
+            line
+        )
+        .expect("writing to a String never fails");
+
+        bytes.extend(encoded);
+    }
+
+    Ok(match format {
+        Format::Raw => bytes,
+        Format::Hex => bytes
+            .chunks(16)
+            .map(|chunk| hex(chunk) + "\n")
+            .collect::<String>()
+            .into_bytes(),
+        Format::Listing => listing.into_bytes(),
+    })
+}
+
+fn run() -> Result<(), String> {
+    let arguments = Arguments::parse()
+        .map_err(|e| format!("{}\nTry 'newton-asm --help' for more information", e))?;
+    let Some(arguments) = arguments else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    // Read the source code
+    let (name, source) = if arguments.input == "-" {
+        let mut source = String::new();
+        io::stdin()
+            .read_to_string(&mut source)
+            .map_err(|e| format!("<stdin>: {}", e))?;
+        ("<stdin>", source)
+    } else {
+        let source = fs::read_to_string(&arguments.input)
+            .map_err(|e| format!("{}: {}", arguments.input, e))?;
+        (arguments.input.as_str(), source)
+    };
+
+    let output = assemble(name, &source, arguments.format)?;
+
+    // Write the result
+    if arguments.output == "-" {
+        io::stdout()
+            .write_all(&output)
+            .map_err(|e| format!("<stdout>: {}", e))
+    } else {
+        fs::write(&arguments.output, output).map_err(|e| format!("{}: {}", arguments.output, e))
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("newton-asm: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,290 @@
+//! Disassemble Prism Binary Format back into Prism Assembly Language
+
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::process::ExitCode;
+
+use libnewton::error::Error;
+use libnewton::instruction::InstructionSet;
+use libnewton::proto;
+
+const USAGE: &str = "\
+Usage: newton-disasm [OPTIONS] [INPUT]
+
+Disassemble Prism Binary Format into Prism Assembly Language (PAL)
+
+Arguments:
+  [INPUT]                  Binary file, '-' reads from stdin [default: -]
+
+Options:
+  -x, --hex                Input is text with hexadecimal bytes instead of raw bytes
+  -b, --bytes <HEX>        Disassemble the given hexadecimal string instead of INPUT
+  -h, --help               Print this message";
+
+/// Where to read the bytes from
+enum Input {
+    /// Read a file or stdin ('-'), [hex] if it contains text instead of raw bytes
+    File { path: String, hex: bool },
+    /// Hexadecimal string given as an argument
+    Bytes(String),
+}
+
+impl Input {
+    /// Parse the command line arguments, returns [None] if help was requested
+    fn parse() -> Result<Option<Self>, String> {
+        let mut path = None;
+        let mut hex = false;
+        let mut bytes = None;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-x" | "--hex" => hex = true,
+                "-b" | "--bytes" => {
+                    bytes = Some(
+                        args.next()
+                            .ok_or_else(|| format!("missing value for '{}'", arg))?,
+                    )
+                }
+                _ if arg.starts_with('-') && arg != "-" => {
+                    return Err(format!("unknown option '{}'", arg))
+                }
+                _ if path.is_none() => path = Some(arg),
+                _ => return Err(format!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        match (bytes, path) {
+            (Some(_), Some(_)) => Err(String::from("cannot use both INPUT and '--bytes'")),
+            (Some(bytes), None) => Ok(Some(Input::Bytes(bytes))),
+            (None, path) => Ok(Some(Input::File {
+                path: path.unwrap_or_else(|| String::from("-")),
+                hex,
+            })),
+        }
+    }
+
+    /// Read every byte from the input
+    fn read(self) -> Result<Vec<u8>, String> {
+        match self {
+            Input::Bytes(text) => parse_hex(&text).map_err(|e| format!("--bytes: {}", e)),
+            Input::File { path, hex } => {
+                let mut bytes = vec![];
+                let result = if path == "-" {
+                    io::stdin().read_to_end(&mut bytes).map(|_| ())
+                } else {
+                    fs::read(&path).map(|contents| bytes = contents)
+                };
+
+                let name = if path == "-" { "<stdin>" } else { &path };
+                result.map_err(|e| format!("{}: {}", name, e))?;
+
+                if hex {
+                    let text = String::from_utf8_lossy(&bytes);
+                    parse_hex(&text).map_err(|e| format!("{}: {}", name, e))
+                } else {
+                    Ok(bytes)
+                }
+            }
+        }
+    }
+}
+
+/// Parse hexadecimal bytes separated (or not) by whitespace or commas, `0x` prefixes are allowed
+fn parse_hex(text: &str) -> Result<Vec<u8>, String> {
+    let digits: String = text
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .map(|word| word.trim_start_matches("0x").trim_start_matches("0X"))
+        .collect();
+
+    if let Some(c) = digits.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("'{}' is not a hexadecimal digit", c));
+    }
+
+    if !digits.len().is_multiple_of(2) {
+        return Err(String::from("odd amount of hexadecimal digits"));
+    }
+
+    Ok((0..digits.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&digits[i..i + 2], 16).expect("digits were validated"))
+        .collect())
+}
+
+/// Write the bytes as hexadecimal pairs separated by spaces
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Result of decoding the bytes at a given offset
+struct Decoded {
+    offset: usize,
+    length: usize,
+    /// Offset where the current script starts, branching targets are relative to it
+    base: usize,
+    result: Result<InstructionSet, Error>,
+}
+
+/// Decode every instruction, undecodable bytes are skipped one at a time
+fn decode(bytes: &[u8]) -> Vec<Decoded> {
+    let mut decoded = vec![];
+    let mut offset = 0;
+    let mut base = 0;
+
+    while offset < bytes.len() {
+        let (result, length) = match InstructionSet::decode(&bytes[offset..]) {
+            Ok((instruction, length)) => (Ok(instruction), length),
+            // Nothing else can be decoded
+            Err(error @ Error::Truncated { .. }) => (Err(error), bytes.len() - offset),
+            // Try again with the next byte
+            Err(error) => (Err(error), 1),
+        };
+
+        decoded.push(Decoded {
+            offset,
+            length,
+            base,
+            result,
+        });
+
+        offset += length;
+
+        // Positions restart on every script
+        if let Some(Ok(InstructionSet::BEGIN)) = decoded.last().map(|d| d.result.as_ref()) {
+            base = offset;
+        }
+    }
+
+    decoded
+}
+
+/// Branching target (C parameter) of JMP, BEQ and BNE
+fn target(instruction: &InstructionSet) -> Option<u8> {
+    match instruction {
+        InstructionSet::JMP(target)
+        | InstructionSet::BEQ(_, _, target)
+        | InstructionSet::BNE(_, _, target) => Some(*target),
+        _ => None,
+    }
+}
+
+/// Write a branching instruction as PAL using a [label] as its target
+fn with_label(instruction: &InstructionSet, label: &str) -> String {
+    match instruction {
+        InstructionSet::JMP(_) => format!("JMP {}", label),
+        InstructionSet::BEQ(a, b, _) => format!("BEQ {} {} {}", a, b, label),
+        InstructionSet::BNE(a, b, _) => format!("BNE {} {} {}", a, b, label),
+        instruction => instruction.to_string(),
+    }
+}
+
+/// Name of the addressing mode used by the instruction byte, if it has addressable parameters
+fn addressing(instruction: &InstructionSet, byte: u8) -> Option<&'static str> {
+    match instruction {
+        InstructionSet::NOP
+        | InstructionSet::BEGIN
+        | InstructionSet::RUN
+        | InstructionSet::TRANSMIT
+        | InstructionSet::AIDX
+        | InstructionSet::RIDX
+        | InstructionSet::HOLD
+        | InstructionSet::NHOLD
+        | InstructionSet::UPDATE
+        | InstructionSet::PAUSE
+        | InstructionSet::RESET
+        | InstructionSet::JMP(_)
+        | InstructionSet::GET(_) => None,
+        _ => proto::AddressingMode::try_from((byte & 0b11) as i32)
+            .ok()
+            .map(|mode| mode.as_str_name()),
+    }
+}
+
+/// Write the disassembled instructions as PAL, offsets and bytes are written as comments\
+/// returns whether every byte could be decoded
+fn disassemble(bytes: &[u8]) -> bool {
+    let decoded = decode(bytes);
+
+    // Every (script, position) where an instruction starts
+    let starts: HashMap<(usize, usize), usize> = decoded
+        .iter()
+        .filter(|d| d.result.is_ok())
+        .map(|d| ((d.base, d.offset - d.base), d.offset))
+        .collect();
+
+    // Synthesize a label for every valid branching target
+    let mut labels: HashMap<usize, String> = HashMap::new();
+    for d in &decoded {
+        if let Some(target) = d.result.as_ref().ok().and_then(target) {
+            let position = target as usize;
+            if let Some(&offset) = starts.get(&(d.base, position)) {
+                labels.insert(offset, format!("label_{}", position));
+            }
+        }
+    }
+
+    let mut valid = true;
+    for d in &decoded {
+        let raw = &bytes[d.offset..d.offset + d.length];
+        let comment = format!("// {:04x}: {}", d.offset, hex(raw));
+
+        let instruction = match &d.result {
+            Ok(instruction) => instruction,
+            Err(error) => {
+                println!("{:<40} {} ({})", "", comment, error);
+                valid = false;
+                continue;
+            }
+        };
+
+        if let Some(label) = labels.get(&d.offset) {
+            println!("{}:", label);
+        }
+
+        // Replace the branching target with its label
+        let text = match target(instruction).and_then(|target| {
+            starts
+                .get(&(d.base, target as usize))
+                .and_then(|offset| labels.get(offset))
+        }) {
+            Some(label) => with_label(instruction, label),
+            None => instruction.to_string(),
+        };
+
+        let text = format!("    {}", text);
+        match addressing(instruction, raw[0]) {
+            Some(mode) => println!("{:<40} {} ({})", text, comment, mode),
+            None => println!("{:<40} {}", text, comment),
+        }
+    }
+
+    valid
+}
+
+fn run() -> Result<bool, String> {
+    let input = Input::parse()
+        .map_err(|e| format!("{}\nTry 'newton-disasm --help' for more information", e))?;
+    let Some(input) = input else {
+        println!("{}", USAGE);
+        return Ok(true);
+    };
+
+    Ok(disassemble(&input.read()?))
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("newton-disasm: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,475 @@
+//! Device emulator, runs the reference interpreter behind a socket as if it were LED hardware
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::{TcpListener, TcpStream};
+#[cfg(unix)]
+use std::os::unix::fs::FileTypeExt;
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::process::ExitCode;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use libnewton::arrays::Array3;
+use libnewton::instruction::InstructionSet;
+use libnewton::machine::{LedBuffer, Machine, Step};
+use libnewton::response::{encode_register_into, REGISTER_RESPONSE_LENGTH};
+
+const USAGE: &str = "\
+Usage: newton-emu [OPTIONS]
+
+Emulate a device: receive Prism Binary Format from a controller, execute it and answer GET
+
+Options:
+  -l, --listen <ADDR>         TCP address for the controller [default: 127.0.0.1:7070]
+      --unix <PATH>           Unix socket for the controller (instead of TCP)
+  -n, --leds <N>              Amount of LEDs in the strip [default: 60]
+      --leds-listen <ADDR>    TCP address that sends the LED buffer (JSON) to every connection
+      --leds-unix <PATH>      Unix socket that sends the LED buffer (JSON) to every connection
+      --leds-file <PATH>      File rewritten with the LED buffer (JSON) whenever it changes
+      --no-delay              Do not wait on DELAY
+  -h, --help                  Print this message";
+
+/// Amount of script instructions executed before checking the sockets again
+const BATCH: usize = 1000;
+
+/// How long to wait for the controller before running the script again
+const TICK: Duration = Duration::from_millis(1);
+
+/// Command line arguments
+struct Arguments {
+    listen: String,
+    unix: Option<String>,
+    leds: usize,
+    leds_listen: Option<String>,
+    leds_unix: Option<String>,
+    leds_file: Option<String>,
+    no_delay: bool,
+}
+
+impl Arguments {
+    /// Parse the command line arguments, returns [None] if help was requested
+    fn parse() -> Result<Option<Self>, String> {
+        let mut arguments = Self {
+            listen: String::from("127.0.0.1:7070"),
+            unix: None,
+            leds: 60,
+            leds_listen: None,
+            leds_unix: None,
+            leds_file: None,
+            no_delay: false,
+        };
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for '{}'", arg))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-l" | "--listen" => arguments.listen = value()?,
+                "--unix" => arguments.unix = Some(value()?),
+                "-n" | "--leds" => {
+                    let value = value()?;
+                    arguments.leds = value
+                        .parse()
+                        .map_err(|_| format!("'{}' is not a valid number", value))?;
+                }
+                "--leds-listen" => arguments.leds_listen = Some(value()?),
+                "--leds-unix" => arguments.leds_unix = Some(value()?),
+                "--leds-file" => arguments.leds_file = Some(value()?),
+                "--no-delay" => arguments.no_delay = true,
+                _ => return Err(format!("unknown option '{}'", arg)),
+            }
+        }
+
+        Ok(Some(arguments))
+    }
+}
+
+/// Connection to the emulator
+trait Stream: Read + Write {}
+impl Stream for TcpStream {}
+#[cfg(unix)]
+impl Stream for UnixStream {}
+
+/// Socket waiting for connections
+enum Endpoint {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+}
+
+impl Endpoint {
+    /// Listen on a TCP address
+    fn tcp(address: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(address).map_err(|e| format!("{}: {}", address, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("{}: {}", address, e))?;
+        Ok(Endpoint::Tcp(listener))
+    }
+
+    /// Listen on a Unix socket, a stale socket file (nobody listening) is replaced but any other
+    /// file is kept
+    #[cfg(unix)]
+    fn unix(path: &str) -> Result<Self, String> {
+        match fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.file_type().is_socket() => {
+                if UnixStream::connect(path).is_ok() {
+                    return Err(format!("{}: address in use", path));
+                }
+                fs::remove_file(path).map_err(|e| format!("{}: {}", path, e))?
+            }
+            Ok(_) => return Err(format!("{}: file exists and is not a socket", path)),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {}
+            Err(error) => return Err(format!("{}: {}", path, error)),
+        }
+
+        let listener = UnixListener::bind(path).map_err(|e| format!("{}: {}", path, e))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| format!("{}: {}", path, e))?;
+        Ok(Endpoint::Unix(listener))
+    }
+
+    #[cfg(not(unix))]
+    fn unix(path: &str) -> Result<Self, String> {
+        Err(format!("{}: unix sockets are not supported", path))
+    }
+
+    /// Accept a pending connection without blocking, reads on the stream wait up to [TICK]
+    fn accept(&self) -> io::Result<Option<Box<dyn Stream>>> {
+        let result = match self {
+            Endpoint::Tcp(listener) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(false)?;
+                stream.set_nodelay(true)?;
+                stream.set_read_timeout(Some(TICK))?;
+                Ok(Box::new(stream) as Box<dyn Stream>)
+            }),
+            #[cfg(unix)]
+            Endpoint::Unix(listener) => listener.accept().and_then(|(stream, _)| {
+                stream.set_nonblocking(false)?;
+                stream.set_read_timeout(Some(TICK))?;
+                Ok(Box::new(stream) as Box<dyn Stream>)
+            }),
+        };
+
+        match result {
+            Ok(stream) => Ok(Some(stream)),
+            Err(error) if error.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+}
+
+/// LED strip that remembers whether its changes were shown
+struct Strip {
+    colors: Vec<Array3<u8>>,
+    changed: bool,
+}
+
+impl LedBuffer for Strip {
+    fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    fn get(&self, index: usize) -> Array3<u8> {
+        self.colors[index]
+    }
+
+    fn set(&mut self, index: usize, color: Array3<u8>) {
+        self.colors[index] = color;
+    }
+
+    fn show(&mut self) {
+        self.changed = true;
+    }
+}
+
+impl Strip {
+    /// HSL components of every LED as a single line of JSON
+    fn json(&self) -> String {
+        let colors: Vec<String> = self
+            .colors
+            .iter()
+            .map(|Array3(h, s, l)| format!("[{},{},{}]", h, s, l))
+            .collect();
+        format!(
+            "{{\"leds\":{},\"colors\":[{}]}}\n",
+            self.colors.len(),
+            colors.join(",")
+        )
+    }
+}
+
+/// Execute a single step, errors are reported to stderr
+fn step(machine: &mut Machine<Strip>) -> Option<Step> {
+    match machine.step() {
+        Ok(step) => Some(step),
+        Err(error) => {
+            eprintln!("newton-emu: {}", error);
+            None
+        }
+    }
+}
+
+/// Execute the received instructions and then the running script until it is delayed, finishes
+/// or [BATCH] instructions are executed, [resume] is when a delayed script continues\
+/// Starting a new script (or stopping the current one) cancels the delay
+fn execute(machine: &mut Machine<Strip>, resume: &mut Instant, no_delay: bool) {
+    // Received instructions take precedence and run even while the script is delayed
+    while machine.has_input() {
+        if let Some(Step::Executed(
+            InstructionSet::BEGIN
+            | InstructionSet::RUN
+            | InstructionSet::RESET
+            | InstructionSet::TRANSMIT,
+        )) = step(machine)
+        {
+            *resume = Instant::now();
+        }
+    }
+
+    if machine.is_running() && Instant::now() >= *resume {
+        for _ in 0..BATCH {
+            match step(machine) {
+                Some(Step::Delayed(duration)) if !no_delay => {
+                    *resume = Instant::now() + duration;
+                    break;
+                }
+                Some(Step::Idle) => break,
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Rewrite [path] with the LED buffer, the file is replaced at once so readers never see it half written
+fn write_file(path: &str, contents: &str) -> io::Result<()> {
+    let temporary = format!("{}.tmp", path);
+    fs::write(&temporary, contents)?;
+    fs::rename(&temporary, path)
+}
+
+fn run() -> Result<(), String> {
+    let arguments = Arguments::parse()
+        .map_err(|e| format!("{}\nTry 'newton-emu --help' for more information", e))?;
+    let Some(arguments) = arguments else {
+        println!("{}", USAGE);
+        return Ok(());
+    };
+
+    let controller = match &arguments.unix {
+        Some(path) => Endpoint::unix(path)?,
+        None => Endpoint::tcp(&arguments.listen)?,
+    };
+
+    let mut observers = vec![];
+    if let Some(address) = &arguments.leds_listen {
+        observers.push(Endpoint::tcp(address)?);
+    }
+    if let Some(path) = &arguments.leds_unix {
+        observers.push(Endpoint::unix(path)?);
+    }
+
+    let mut machine = Machine::new(Strip {
+        colors: vec![Array3(0, 0, 0); arguments.leds],
+        changed: true,
+    });
+
+    eprintln!(
+        "newton-emu: listening on {} with {} LEDs",
+        arguments.unix.as_deref().unwrap_or(&arguments.listen),
+        arguments.leds
+    );
+
+    let mut client: Option<Box<dyn Stream>> = None;
+    let mut buffer = [0; 1024];
+    let mut resume = Instant::now();
+
+    loop {
+        // A single controller at a time, the device keeps its state between connections
+        match &mut client {
+            None => {
+                client = controller.accept().map_err(|e| e.to_string())?;
+                if client.is_some() {
+                    eprintln!("newton-emu: controller connected");
+                } else {
+                    thread::sleep(TICK);
+                }
+            }
+            Some(stream) => match stream.read(&mut buffer) {
+                Ok(0) => {
+                    eprintln!("newton-emu: controller disconnected");
+                    client = None;
+                }
+                Ok(length) => machine.feed(&buffer[..length]),
+                Err(error)
+                    if matches!(
+                        error.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) => {}
+                Err(error) => {
+                    eprintln!("newton-emu: controller disconnected ({})", error);
+                    client = None;
+                }
+            },
+        }
+
+        execute(&mut machine, &mut resume, arguments.no_delay);
+
+        // Answer GET
+        let responses = machine.take_responses();
+        if let Some(stream) = &mut client {
+            for (register, value) in responses {
+                let mut response = [0; REGISTER_RESPONSE_LENGTH];
+                encode_register_into(register, value, &mut response).map_err(|e| e.to_string())?;
+                if let Err(error) = stream.write_all(&response) {
+                    eprintln!("newton-emu: controller disconnected ({})", error);
+                    client = None;
+                    break;
+                }
+            }
+        }
+
+        // Expose the LED buffer
+        let strip = machine.buffer();
+        for observer in &observers {
+            while let Some(mut stream) = observer.accept().map_err(|e| e.to_string())? {
+                // Observers that do not read are not waited for
+                let _ = stream.write_all(strip.json().as_bytes());
+            }
+        }
+
+        if strip.changed {
+            if let Some(path) = &arguments.leds_file {
+                write_file(path, &strip.json()).map_err(|e| format!("{}: {}", path, e))?;
+            }
+            machine.buffer_mut().changed = false;
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("newton-emu: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use libnewton::binary;
+
+    fn strip(leds: usize) -> Machine<Strip> {
+        Machine::new(Strip {
+            colors: vec![Array3(0, 0, 0); leds],
+            changed: false,
+        })
+    }
+
+    /// Receive [source] and run the emulator loop once
+    fn receive(machine: &mut Machine<Strip>, resume: &mut Instant, source: &str) {
+        machine.feed(&binary::assemble_str(source).unwrap());
+        execute(machine, resume, false);
+    }
+
+    #[test]
+    fn delay_postpones_the_script() {
+        let mut machine = strip(1);
+        let mut resume = Instant::now();
+
+        receive(
+            &mut machine,
+            &mut resume,
+            "BEGIN; DELAY HRS 1; LPAINT 0 50; UPDATE; RUN",
+        );
+        assert!(resume > Instant::now() + Duration::from_secs(3500));
+        assert!(machine.is_running());
+
+        // Still delayed
+        execute(&mut machine, &mut resume, false);
+        assert_eq!(machine.buffer().colors, [Array3(0, 0, 0)]);
+
+        // Unless the delay is ignored
+        resume = Instant::now();
+        execute(&mut machine, &mut resume, true);
+        assert_eq!(machine.buffer().colors, [Array3(0, 0, 50)]);
+        assert!(!machine.is_running());
+    }
+
+    #[test]
+    fn new_scripts_cancel_the_delay() {
+        for restart in [
+            "BEGIN; LPAINT 0 50; UPDATE; RUN",
+            "RESET; BEGIN; LPAINT 0 50; UPDATE; RUN",
+            "TRANSMIT; LPAINT 0 50; UPDATE; BEGIN; RUN",
+        ] {
+            let mut machine = strip(1);
+            let mut resume = Instant::now();
+
+            receive(&mut machine, &mut resume, "BEGIN; DELAY HRS 1; RUN");
+            assert!(resume > Instant::now());
+
+            receive(&mut machine, &mut resume, restart);
+            assert!(resume <= Instant::now(), "{}", restart);
+            assert_eq!(machine.buffer().colors, [Array3(0, 0, 50)], "{}", restart);
+        }
+    }
+
+    #[test]
+    fn transmitted_instructions_run_while_delayed() {
+        let mut machine = strip(2);
+        let mut resume = Instant::now();
+
+        receive(&mut machine, &mut resume, "BEGIN; DELAY HRS 1; RUN");
+        let delayed = resume;
+
+        // The script keeps waiting
+        receive(&mut machine, &mut resume, "AIDX; LPAINT 1 20; UPDATE");
+        assert_eq!(resume, delayed);
+        assert_eq!(machine.buffer().colors, [Array3(0, 0, 0), Array3(0, 0, 20)]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unix_sockets_only_replace_sockets() {
+        let directory = std::env::temp_dir();
+        let socket = directory.join(format!("newton-emu-{}.sock", std::process::id()));
+        let file = directory.join(format!("newton-emu-{}.json", std::process::id()));
+        let socket = socket.to_str().unwrap();
+        let file = file.to_str().unwrap();
+
+        // Stale socket left by a previous run
+        drop(UnixListener::bind(socket).unwrap());
+        let live = Endpoint::unix(socket).unwrap();
+
+        // Socket of a running emulator
+        assert_eq!(
+            Endpoint::unix(socket).err().unwrap(),
+            format!("{}: address in use", socket)
+        );
+        assert!(UnixStream::connect(socket).is_ok());
+        assert!(live.accept().unwrap().is_some());
+
+        drop(live);
+        assert!(Endpoint::unix(socket).is_ok());
+        fs::remove_file(socket).unwrap();
+
+        fs::write(file, "{}").unwrap();
+        assert_eq!(
+            Endpoint::unix(file).err().unwrap(),
+            format!("{}: file exists and is not a socket", file)
+        );
+        assert_eq!(fs::read_to_string(file).unwrap(), "{}");
+        fs::remove_file(file).unwrap();
+    }
+}
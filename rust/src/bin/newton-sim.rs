@@ -0,0 +1,329 @@
+//! Headless simulator, runs a script over a virtual LED strip and dumps every frame
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use libnewton::arrays::Array3;
+use libnewton::binary;
+use libnewton::color::{Hsl, Rgb};
+use libnewton::error::Location;
+use libnewton::instruction::InstructionSet;
+use libnewton::machine::{LedBuffer, Machine, Step};
+
+const USAGE: &str = "\
+Usage: newton-sim [OPTIONS] <INPUT>
+
+Run a script over a virtual LED strip and dump the LED state of every frame
+
+Arguments:
+  <INPUT>                  PAL source or Prism Binary Format file, '-' reads from stdin
+
+Options:
+  -n, --leds <N>           Amount of LEDs in the strip [default: 60]
+      --aidx               Start with absolute indexing instead of relative indexing
+      --pal                Input is PAL source code [default: if INPUT ends in .pal]
+      --binary             Input is Prism Binary Format [default: otherwise]
+  -f, --format <FORMAT>    Output format: text, json or ppm [default: text]
+  -o, --output <PATH>      Output file ('-' for stdout) or directory for ppm [default: - or frames]
+      --scale <PX>         Size in pixels of every LED in ppm images [default: 16]
+      --steps <N>          Stop after executing N instructions [default: 100000]
+      --duration <MS>      Stop after N milliseconds of virtual time [default: 60000]
+  -h, --help               Print this message";
+
+/// How to dump the frames
+#[derive(Copy, Clone)]
+enum Format {
+    /// One line per frame with the HSL components of every LED
+    Text,
+    /// Single JSON document with every frame
+    Json,
+    /// One PPM image per frame
+    Ppm,
+}
+
+/// Command line arguments
+struct Arguments {
+    input: String,
+    pal: Option<bool>,
+    leds: usize,
+    aidx: bool,
+    format: Format,
+    output: Option<String>,
+    scale: usize,
+    steps: usize,
+    duration: Duration,
+}
+
+impl Arguments {
+    /// Parse the command line arguments, returns [None] if help was requested
+    fn parse() -> Result<Option<Self>, String> {
+        let mut arguments = Self {
+            input: String::new(),
+            pal: None,
+            leds: 60,
+            aidx: false,
+            format: Format::Text,
+            output: None,
+            scale: 16,
+            steps: 100_000,
+            duration: Duration::from_secs(60),
+        };
+        let mut input = None;
+
+        let mut args = std::env::args().skip(1);
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .ok_or_else(|| format!("missing value for '{}'", arg))
+            };
+            let number = |value: String| {
+                value
+                    .parse::<usize>()
+                    .map_err(|_| format!("'{}' is not a valid number", value))
+            };
+
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-n" | "--leds" => arguments.leds = number(value()?)?,
+                "--aidx" => arguments.aidx = true,
+                "--pal" => arguments.pal = Some(true),
+                "--binary" => arguments.pal = Some(false),
+                "-f" | "--format" => {
+                    arguments.format = match value()?.as_str() {
+                        "text" => Format::Text,
+                        "json" => Format::Json,
+                        "ppm" => Format::Ppm,
+                        other => return Err(format!("unknown format '{}'", other)),
+                    }
+                }
+                "-o" | "--output" => arguments.output = Some(value()?),
+                "--scale" => arguments.scale = number(value()?)?.max(1),
+                "--steps" => arguments.steps = number(value()?)?,
+                "--duration" => {
+                    arguments.duration = Duration::from_millis(number(value()?)? as u64)
+                }
+                _ if arg.starts_with('-') && arg != "-" => {
+                    return Err(format!("unknown option '{}'", arg))
+                }
+                _ if input.is_none() => input = Some(arg),
+                _ => return Err(format!("unexpected argument '{}'", arg)),
+            }
+        }
+
+        arguments.input = input.ok_or("missing input file")?;
+        Ok(Some(arguments))
+    }
+}
+
+/// State of every LED at a given point in virtual time
+struct Frame {
+    time: Duration,
+    colors: Vec<Array3<u8>>,
+}
+
+/// Virtual LED strip that records a frame whenever its changes are shown
+struct Strip {
+    colors: Vec<Array3<u8>>,
+    time: Duration,
+    frames: Vec<Frame>,
+}
+
+impl LedBuffer for Strip {
+    fn len(&self) -> usize {
+        self.colors.len()
+    }
+
+    fn get(&self, index: usize) -> Array3<u8> {
+        self.colors[index]
+    }
+
+    fn set(&mut self, index: usize, color: Array3<u8>) {
+        self.colors[index] = color;
+    }
+
+    fn show(&mut self) {
+        self.frames.push(Frame {
+            time: self.time,
+            colors: self.colors.clone(),
+        });
+    }
+}
+
+/// Run the script until there is nothing left to execute or a limit is reached\
+/// errors are reported to stderr, returns whether the script ran without errors
+fn simulate(machine: &mut Machine<Strip>, arguments: &Arguments) -> bool {
+    let mut valid = true;
+
+    for _ in 0..arguments.steps {
+        if machine.buffer().time > arguments.duration {
+            break;
+        }
+
+        match machine.step() {
+            Ok(Step::Executed(_)) => {}
+            Ok(Step::Delayed(duration)) => machine.buffer_mut().time += duration,
+            Ok(Step::Idle) => break,
+            Err(error) => {
+                eprintln!("newton-sim: {}", error);
+                valid = false;
+            }
+        }
+    }
+
+    valid
+}
+
+/// Write every frame as a line with its time and the HSL components of every LED
+fn text(frames: &[Frame]) -> String {
+    let mut output = String::new();
+    for frame in frames {
+        let colors: Vec<String> = frame
+            .colors
+            .iter()
+            .map(|Array3(h, s, l)| format!("{},{},{}", h, s, l))
+            .collect();
+        writeln!(
+            output,
+            "{:>8} ms | {}",
+            frame.time.as_millis(),
+            colors.join(" ")
+        )
+        .expect("writing to a String never fails");
+    }
+    output
+}
+
+/// Write every frame as a single JSON document
+fn json(frames: &[Frame], leds: usize) -> String {
+    let frames: Vec<String> = frames
+        .iter()
+        .map(|frame| {
+            let colors: Vec<String> = frame
+                .colors
+                .iter()
+                .map(|Array3(h, s, l)| format!("[{},{},{}]", h, s, l))
+                .collect();
+            format!(
+                "{{\"time_ms\":{},\"colors\":[{}]}}",
+                frame.time.as_millis(),
+                colors.join(",")
+            )
+        })
+        .collect();
+
+    format!("{{\"leds\":{},\"frames\":[{}]}}\n", leds, frames.join(","))
+}
+
+/// Write every frame as a PPM image (a single row of squares) inside [directory]
+fn ppm(frames: &[Frame], directory: &Path, scale: usize) -> io::Result<()> {
+    fs::create_dir_all(directory)?;
+
+    for (index, frame) in frames.iter().enumerate() {
+        let width = frame.colors.len() * scale;
+        let mut image = format!("P6\n{} {}\n255\n", width, scale).into_bytes();
+
+        let row: Vec<u8> = frame
+            .colors
+            .iter()
+            .flat_map(|&color| {
+                let Rgb { red, green, blue } = Hsl::from(color).into();
+                std::iter::repeat_n([red, green, blue], scale).flatten()
+            })
+            .collect();
+
+        for _ in 0..scale {
+            image.extend_from_slice(&row);
+        }
+
+        fs::write(directory.join(format!("frame_{:05}.ppm", index)), image)?;
+    }
+
+    Ok(())
+}
+
+fn run() -> Result<bool, String> {
+    let arguments = Arguments::parse()
+        .map_err(|e| format!("{}\nTry 'newton-sim --help' for more information", e))?;
+    let Some(arguments) = arguments else {
+        println!("{}", USAGE);
+        return Ok(true);
+    };
+
+    // Read the script
+    let (name, bytes) = if arguments.input == "-" {
+        let mut bytes = vec![];
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("<stdin>: {}", e))?;
+        ("<stdin>", bytes)
+    } else {
+        let bytes =
+            fs::read(&arguments.input).map_err(|e| format!("{}: {}", arguments.input, e))?;
+        (arguments.input.as_str(), bytes)
+    };
+
+    let pal = arguments
+        .pal
+        .unwrap_or_else(|| arguments.input.ends_with(".pal"));
+
+    let bytes = if pal {
+        let source = String::from_utf8(bytes).map_err(|e| format!("{}: {}", name, e))?;
+        binary::assemble_str(&source).map_err(|e| match e.location() {
+            Some(Location::Source(position)) => format!("{}:{}: {}", name, position, e.kind()),
+            _ => format!("{}: {}", name, e),
+        })?
+    } else {
+        bytes
+    };
+
+    // Simulate the strip
+    let mut machine = Machine::new(Strip {
+        colors: vec![Array3(0, 0, 0); arguments.leds],
+        time: Duration::ZERO,
+        frames: vec![],
+    });
+
+    if arguments.aidx {
+        machine.feed(&InstructionSet::AIDX.encode().expect("AIDX has no operands"));
+    }
+    machine.feed(&bytes);
+
+    let valid = simulate(&mut machine, &arguments);
+    let frames = &machine.buffer().frames;
+
+    // Dump the frames
+    let output = match arguments.format {
+        Format::Text => text(frames),
+        Format::Json => json(frames, arguments.leds),
+        Format::Ppm => {
+            let directory = arguments.output.as_deref().unwrap_or("frames");
+            ppm(frames, Path::new(directory), arguments.scale)
+                .map_err(|e| format!("{}: {}", directory, e))?;
+            return Ok(valid);
+        }
+    };
+
+    match arguments.output.as_deref() {
+        None | Some("-") => io::stdout()
+            .write_all(output.as_bytes())
+            .map_err(|e| format!("<stdout>: {}", e))?,
+        Some(path) => fs::write(path, output).map_err(|e| format!("{}: {}", path, e))?,
+    }
+
+    Ok(valid)
+}
+
+fn main() -> ExitCode {
+    match run() {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(message) => {
+            eprintln!("newton-sim: {}", message);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,6 +1,65 @@
+use crate::assembler;
+use crate::error::{Error, Location};
 use crate::instruction::InstructionSet;
 
 // Transform source code into to Prism Binary Format
-pub fn assemble(source: &Vec<InstructionSet>) -> Vec<u8> {
-    source.iter().map::<Vec<u8>, _>(move |&x| x.into()).flatten().collect()
-}
\ No newline at end of file
+// Errors are located at the index of the instruction that could not be encoded
+pub fn assemble(source: &[InstructionSet]) -> Result<Vec<u8>, Error> {
+    let mut result = vec![];
+    for (index, instruction) in source.iter().enumerate() {
+        let mut bytes = instruction
+            .encode()
+            .map_err(|e| e.at(Location::Index(index)))?;
+        result.append(&mut bytes);
+    }
+    Ok(result)
+}
+
+/// Parse Prism Assembly Language source code and transform it into Prism Binary Format
+pub fn assemble_str(source: &str) -> Result<Vec<u8>, Error> {
+    assemble(&assembler::parse(source)?)
+}
+
+/// Iterator over the instructions in Prism Binary Format alongside their byte offset\
+/// Stops after the first error, errors are located at the offset of the failing instruction
+pub struct Disassembler<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Disassembler<'a> {
+    /// Iterate over the instructions stored in [bytes]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, offset: 0 }
+    }
+}
+
+impl Iterator for Disassembler<'_> {
+    type Item = Result<(usize, InstructionSet), Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.offset >= self.bytes.len() {
+            return None;
+        }
+
+        let offset = self.offset;
+        match InstructionSet::decode(&self.bytes[offset..]) {
+            Ok((instruction, length)) => {
+                self.offset += length;
+                Some(Ok((offset, instruction)))
+            }
+            Err(error) => {
+                // Do not continue decoding after an error
+                self.offset = self.bytes.len();
+                Some(Err(error.at(Location::Offset(offset))))
+            }
+        }
+    }
+}
+
+/// Transform Prism Binary Format back into a set of instructions
+pub fn disassemble(bytes: &[u8]) -> Result<Vec<InstructionSet>, Error> {
+    Disassembler::new(bytes)
+        .map(|result| result.map(|(_, instruction)| instruction))
+        .collect()
+}
@@ -0,0 +1,486 @@
+use crate::addressing::AddressingMode;
+use crate::arrays::{Array2, Array3};
+use crate::binary;
+use crate::codes::{DelayCode, EffectCode};
+use crate::error::{Error, Location};
+use crate::instruction::{InstructionSet, SCRIPT_SIZE};
+use crate::labels::{self, Item};
+use crate::registers::Register;
+
+/// Fluent API for building a script without writing Prism Assembly Language\
+/// every call adds an instruction (or label), the script must start with [ScriptBuilder::begin]
+/// and is assembled when [ScriptBuilder::run] adds the final RUN
+///
+/// ```
+/// use libnewton::builder::ScriptBuilder;
+/// use libnewton::color::hsl;
+///
+/// let bytes = ScriptBuilder::new()
+///     .begin()
+///     .label("loop")
+///     .fill(0..50, hsl(120, 255, 128))
+///     .delay_ms(200)
+///     .jmp_label("loop")
+///     .run()
+///     .unwrap();
+///
+/// assert_eq!(
+///     bytes,
+///     [0x04, 0x4c, 0, 50, 120, 255, 128, 0x70, 0, 200, 0x28, 0, 0x08]
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ScriptBuilder {
+    items: Vec<Item>,
+}
+
+impl ScriptBuilder {
+    /// Create an empty script
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add any instruction, branching targets are taken as positions in the script
+    pub fn instruction(mut self, instruction: InstructionSet) -> Self {
+        self.items.push(Item::Instruction(instruction));
+        self
+    }
+
+    /// Name the position of the next instruction, can be used with the `*_label` methods
+    pub fn label(mut self, label: &str) -> Self {
+        self.items.push(Item::Label(label.to_string()));
+        self
+    }
+
+    /// Add a branching instruction whose target is the position of [label]
+    fn branch(mut self, instruction: InstructionSet, label: &str) -> Self {
+        self.items
+            .push(Item::Branch(instruction, label.to_string()));
+        self
+    }
+
+    /// BEGIN, must be the first instruction
+    pub fn begin(self) -> Self {
+        self.instruction(InstructionSet::BEGIN)
+    }
+
+    /// NOP
+    pub fn nop(self) -> Self {
+        self.instruction(InstructionSet::NOP)
+    }
+
+    /// HALT with the given status code
+    pub fn halt(self, code: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::HALT(code.into()))
+    }
+
+    /// AIDX, use absolute indexing
+    pub fn aidx(self) -> Self {
+        self.instruction(InstructionSet::AIDX)
+    }
+
+    /// RIDX, use relative indexing
+    pub fn ridx(self) -> Self {
+        self.instruction(InstructionSet::RIDX)
+    }
+
+    /// HOLD
+    pub fn hold(self) -> Self {
+        self.instruction(InstructionSet::HOLD)
+    }
+
+    /// NHOLD
+    pub fn nhold(self) -> Self {
+        self.instruction(InstructionSet::NHOLD)
+    }
+
+    /// UPDATE
+    pub fn update(self) -> Self {
+        self.instruction(InstructionSet::UPDATE)
+    }
+
+    /// JMP to a position in the script
+    pub fn jmp(self, position: u8) -> Self {
+        self.instruction(InstructionSet::JMP(position))
+    }
+
+    /// JMP to a label
+    pub fn jmp_label(self, label: &str) -> Self {
+        self.branch(InstructionSet::JMP(0), label)
+    }
+
+    /// RET with the given return value
+    pub fn ret(self, value: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::RET(value.into()))
+    }
+
+    /// BEQ to a position in the script
+    pub fn beq(
+        self,
+        a: impl Into<AddressingMode>,
+        b: impl Into<AddressingMode>,
+        position: u8,
+    ) -> Self {
+        self.instruction(InstructionSet::BEQ(a.into(), b.into(), position))
+    }
+
+    /// BEQ to a label
+    pub fn beq_label(
+        self,
+        a: impl Into<AddressingMode>,
+        b: impl Into<AddressingMode>,
+        label: &str,
+    ) -> Self {
+        self.branch(InstructionSet::BEQ(a.into(), b.into(), 0), label)
+    }
+
+    /// BNE to a position in the script
+    pub fn bne(
+        self,
+        a: impl Into<AddressingMode>,
+        b: impl Into<AddressingMode>,
+        position: u8,
+    ) -> Self {
+        self.instruction(InstructionSet::BNE(a.into(), b.into(), position))
+    }
+
+    /// BNE to a label
+    pub fn bne_label(
+        self,
+        a: impl Into<AddressingMode>,
+        b: impl Into<AddressingMode>,
+        label: &str,
+    ) -> Self {
+        self.branch(InstructionSet::BNE(a.into(), b.into(), 0), label)
+    }
+
+    /// LOAD a value into a register
+    pub fn load(self, register: Register, value: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::LOAD(register, value.into()))
+    }
+
+    /// ADD a value to a register
+    pub fn add(self, register: Register, value: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::ADD(register, value.into()))
+    }
+
+    /// SUB a value from a register
+    pub fn sub(self, register: Register, value: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::SUB(register, value.into()))
+    }
+
+    /// FILL a range with a color (e.g. `fill(0..50, hsl(120, 255, 128))`)
+    pub fn fill(
+        self,
+        range: impl Into<Array2<AddressingMode>>,
+        color: impl Into<Array3<AddressingMode>>,
+    ) -> Self {
+        self.instruction(InstructionSet::FILL(range.into(), color.into()))
+    }
+
+    /// HFILL a range with a hue
+    pub fn hfill(
+        self,
+        range: impl Into<Array2<AddressingMode>>,
+        hue: impl Into<AddressingMode>,
+    ) -> Self {
+        self.instruction(InstructionSet::HFILL(range.into(), hue.into()))
+    }
+
+    /// SFILL a range with a saturation
+    pub fn sfill(
+        self,
+        range: impl Into<Array2<AddressingMode>>,
+        saturation: impl Into<AddressingMode>,
+    ) -> Self {
+        self.instruction(InstructionSet::SFILL(range.into(), saturation.into()))
+    }
+
+    /// LFILL a range with a lightness
+    pub fn lfill(
+        self,
+        range: impl Into<Array2<AddressingMode>>,
+        lightness: impl Into<AddressingMode>,
+    ) -> Self {
+        self.instruction(InstructionSet::LFILL(range.into(), lightness.into()))
+    }
+
+    /// PAINT a single LED with a color
+    pub fn paint(
+        self,
+        index: impl Into<AddressingMode>,
+        color: impl Into<Array3<AddressingMode>>,
+    ) -> Self {
+        self.instruction(InstructionSet::PAINT(index.into(), color.into()))
+    }
+
+    /// HPAINT a single LED with a hue
+    pub fn hpaint(self, index: impl Into<AddressingMode>, hue: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::HPAINT(index.into(), hue.into()))
+    }
+
+    /// SPAINT a single LED with a saturation
+    pub fn spaint(
+        self,
+        index: impl Into<AddressingMode>,
+        saturation: impl Into<AddressingMode>,
+    ) -> Self {
+        self.instruction(InstructionSet::SPAINT(index.into(), saturation.into()))
+    }
+
+    /// LPAINT a single LED with a lightness
+    pub fn lpaint(
+        self,
+        index: impl Into<AddressingMode>,
+        lightness: impl Into<AddressingMode>,
+    ) -> Self {
+        self.instruction(InstructionSet::LPAINT(index.into(), lightness.into()))
+    }
+
+    /// EFFECT over a range
+    pub fn effect(
+        self,
+        code: EffectCode,
+        range: impl Into<Array2<AddressingMode>>,
+        value: impl Into<AddressingMode>,
+    ) -> Self {
+        self.instruction(InstructionSet::EFFECT(code, range.into(), value.into()))
+    }
+
+    /// DELAY for an amount of time in the given unit
+    pub fn delay(self, code: DelayCode, amount: impl Into<AddressingMode>) -> Self {
+        self.instruction(InstructionSet::DELAY(code, amount.into()))
+    }
+
+    /// DELAY for milliseconds
+    pub fn delay_ms(self, amount: impl Into<AddressingMode>) -> Self {
+        self.delay(DelayCode::MS, amount)
+    }
+
+    /// DELAY for seconds
+    pub fn delay_sec(self, amount: impl Into<AddressingMode>) -> Self {
+        self.delay(DelayCode::SEC, amount)
+    }
+
+    /// DELAY for minutes
+    pub fn delay_min(self, amount: impl Into<AddressingMode>) -> Self {
+        self.delay(DelayCode::MIN, amount)
+    }
+
+    /// DELAY for hours
+    pub fn delay_hrs(self, amount: impl Into<AddressingMode>) -> Self {
+        self.delay(DelayCode::HRS, amount)
+    }
+
+    /// PAUSE
+    pub fn pause(self) -> Self {
+        self.instruction(InstructionSet::PAUSE)
+    }
+
+    /// GET the contents of a register
+    pub fn get(self, register: Register) -> Self {
+        self.instruction(InstructionSet::GET(register))
+    }
+
+    /// Add the final RUN and assemble the script into Prism Binary Format\
+    /// fails if the script does not start with BEGIN, if BEGIN, RUN, TRANSMIT or RESET appear anywhere else,
+    /// if a label cannot be resolved, if an instruction cannot be encoded or if the script does not fit
+    /// in [SCRIPT_SIZE] bytes\
+    /// errors are located at the index of the call (instruction or label) that caused them
+    pub fn run(self) -> Result<Vec<u8>, Error> {
+        let items = self.instruction(InstructionSet::RUN).items;
+        let last = items.len() - 1;
+
+        if items.first() != Some(&Item::Instruction(InstructionSet::BEGIN)) {
+            return Err(Error::ExpectedBegin.at(Location::Index(0)));
+        }
+
+        // Control instructions would end (or restart) the script prematurely
+        for (index, item) in items.iter().enumerate().take(last).skip(1) {
+            if let Item::Instruction(
+                instruction @ (InstructionSet::BEGIN
+                | InstructionSet::RUN
+                | InstructionSet::TRANSMIT
+                | InstructionSet::RESET),
+            ) = item
+            {
+                return Err(Error::MisplacedInstruction(*instruction).at(Location::Index(index)));
+            }
+        }
+
+        let instructions = labels::resolve(&items)?;
+
+        // Labels are removed when resolving, locate encoding errors at their original item
+        let indices: Vec<usize> = items
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| !matches!(item, Item::Label(_)))
+            .map(|(index, _)| index)
+            .collect();
+
+        let bytes = binary::assemble(&instructions).map_err(|e| match e.location() {
+            Some(Location::Index(index)) => e.kind().clone().at(Location::Index(indices[index])),
+            _ => e,
+        })?;
+
+        // Devices store everything between BEGIN and RUN, locate the first instruction that does not fit
+        let mut size = 0;
+        let body = instructions.len() - 1;
+        for (index, instruction) in instructions.iter().enumerate().take(body).skip(1) {
+            size += instruction.size();
+            if size > SCRIPT_SIZE {
+                return Err(Error::ScriptOverflow.at(Location::Index(indices[index])));
+            }
+        }
+
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::color::Hsl;
+
+    /// Script made of [count] PAINT instructions (5 bytes each) and a label
+    fn paints(count: usize) -> ScriptBuilder {
+        (0..count).fold(ScriptBuilder::new().begin().label("start"), |builder, i| {
+            builder.paint(i as u8, Hsl::RED)
+        })
+    }
+
+    #[test]
+    fn script_fills_the_device_memory() {
+        let bytes = paints(SCRIPT_SIZE / 5).run().unwrap();
+        assert_eq!(bytes.len(), SCRIPT_SIZE + 2);
+    }
+
+    #[test]
+    fn script_overflow_is_located_at_the_call() {
+        // BEGIN and the label come before the first PAINT
+        assert_eq!(
+            paints(SCRIPT_SIZE / 5 + 1).run(),
+            Err(Error::ScriptOverflow.at(Location::Index(SCRIPT_SIZE / 5 + 2)))
+        );
+    }
+
+    #[test]
+    fn labels_are_resolved() {
+        let bytes = ScriptBuilder::new()
+            .begin()
+            .label("top")
+            .nop()
+            .bne_label(Register::R0, 3, "top")
+            .beq_label(Register::R0, 0, "end")
+            .jmp_label("top")
+            .label("end")
+            .run()
+            .unwrap();
+
+        assert_eq!(
+            bytes,
+            binary::assemble_str(
+                "BEGIN; top: NOP; BNE $R0 3 top; BEQ $R0 0 end; JMP top; end: RUN"
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn script_must_start_with_begin() {
+        for builder in [
+            ScriptBuilder::new(),
+            ScriptBuilder::new().nop().begin(),
+            ScriptBuilder::new().label("start").begin(),
+        ] {
+            assert_eq!(
+                builder.run(),
+                Err(Error::ExpectedBegin.at(Location::Index(0)))
+            );
+        }
+    }
+
+    #[test]
+    fn control_instructions_inside_the_script() {
+        for instruction in [
+            InstructionSet::BEGIN,
+            InstructionSet::RUN,
+            InstructionSet::TRANSMIT,
+            InstructionSet::RESET,
+        ] {
+            let builder = ScriptBuilder::new()
+                .begin()
+                .label("start")
+                .nop()
+                .instruction(instruction)
+                .nop();
+            assert_eq!(
+                builder.run(),
+                Err(Error::MisplacedInstruction(instruction).at(Location::Index(3)))
+            );
+        }
+    }
+
+    #[test]
+    fn label_errors_are_located_at_the_call() {
+        assert_eq!(
+            ScriptBuilder::new()
+                .begin()
+                .nop()
+                .jmp_label("nowhere")
+                .run(),
+            Err(Error::UndefinedLabel(String::from("nowhere")).at(Location::Index(2)))
+        );
+        assert_eq!(
+            ScriptBuilder::new()
+                .begin()
+                .label("twice")
+                .nop()
+                .label("twice")
+                .beq_label(Register::R0, 0, "twice")
+                .run(),
+            Err(Error::DuplicatedLabel(String::from("twice")).at(Location::Index(3)))
+        );
+
+        // 52 PAINT take 260 bytes
+        assert_eq!(
+            paints(52).label("far").nop().beq_label(0, 0, "far").run(),
+            Err(Error::LabelOutOfRange(String::from("far"), 260).at(Location::Index(56)))
+        );
+    }
+
+    #[test]
+    fn encoding_errors_skip_labels() {
+        let mixed = InstructionSet::FILL(
+            Array2(
+                AddressingMode::Immediate(0),
+                AddressingMode::Indirect(Register::R0),
+            ),
+            Array3(
+                AddressingMode::Immediate(0),
+                AddressingMode::Immediate(0),
+                AddressingMode::Immediate(0),
+            ),
+        );
+
+        // The FILL is the second instruction but the fifth call
+        assert_eq!(
+            ScriptBuilder::new()
+                .begin()
+                .label("a")
+                .label("b")
+                .nop()
+                .instruction(mixed)
+                .run(),
+            Err(Error::MixedAddressing.at(Location::Index(4)))
+        );
+        assert_eq!(
+            ScriptBuilder::new()
+                .begin()
+                .label("a")
+                .effect(EffectCode::Custom(1), 0..10, 5)
+                .run(),
+            Err(Error::StandardEffectCode(1).at(Location::Index(2)))
+        );
+    }
+}
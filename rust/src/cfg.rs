@@ -0,0 +1,505 @@
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::instruction::InstructionSet;
+use crate::lint::{Diagnostic, Rule};
+
+/// Where the execution continues after a [BasicBlock]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Successor {
+    /// Index of the following block
+    Block(usize),
+    /// The script finishes (the position is past its last instruction)
+    Exit,
+}
+
+/// Instructions that always execute in sequence, only the first one can be a branching target
+/// and only the last one can branch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    /// Byte offsets [start, end) of the block, counted from the instruction following BEGIN
+    pub offsets: Range<usize>,
+    /// Indices of the instructions of the block
+    pub instructions: Range<usize>,
+    /// Where the execution may continue after the last instruction
+    pub successors: Vec<Successor>,
+}
+
+/// Control-flow graph of a script, made of [BasicBlock]s sorted by their byte offset
+#[derive(Debug, Clone)]
+pub struct ControlFlowGraph {
+    instructions: Vec<InstructionSet>,
+    /// Byte offset of every instruction
+    offsets: Vec<usize>,
+    /// Instructions considered part of the script (a leading BEGIN and RUN onwards are excluded)
+    script: Range<usize>,
+    /// Size of the script in bytes
+    size: usize,
+    blocks: Vec<BasicBlock>,
+}
+
+/// Target of a branching instruction
+fn target(instruction: InstructionSet) -> Option<u8> {
+    match instruction {
+        InstructionSet::JMP(position)
+        | InstructionSet::BEQ(_, _, position)
+        | InstructionSet::BNE(_, _, position) => Some(position),
+        _ => None,
+    }
+}
+
+/// Whether the instruction gives control back to the device (for a while or forever)
+fn yields(instruction: InstructionSet) -> bool {
+    matches!(
+        instruction,
+        InstructionSet::DELAY(..) | InstructionSet::PAUSE | InstructionSet::HALT(_)
+    )
+}
+
+impl ControlFlowGraph {
+    /// Build the graph of the script, a leading BEGIN is skipped and the script ends on the first RUN\
+    /// Indices of the instructions (see [BasicBlock::instructions]) refer to [instructions]\
+    /// RET may return after any JMP, BEQ or BNE (to the start of the script if there are none)
+    pub fn new(instructions: &[InstructionSet]) -> Self {
+        let start = usize::from(instructions.first() == Some(&InstructionSet::BEGIN));
+        let end = instructions[start..]
+            .iter()
+            .position(|&instruction| instruction == InstructionSet::RUN)
+            .map_or(instructions.len(), |position| start + position);
+
+        let mut offsets = vec![0; instructions.len()];
+        let mut offset = 0;
+        for index in start..end {
+            offsets[index] = offset;
+            offset += instructions[index].size();
+        }
+
+        let mut graph = Self {
+            instructions: instructions.to_vec(),
+            offsets,
+            script: start..end,
+            size: offset,
+            blocks: vec![],
+        };
+        graph.build();
+        graph
+    }
+
+    /// Split the script into blocks and link them
+    fn build(&mut self) {
+        let script = self.script.clone();
+        let branching = |instruction: InstructionSet| {
+            target(instruction).is_some()
+                || matches!(
+                    instruction,
+                    InstructionSet::RET(_) | InstructionSet::HALT(_)
+                )
+        };
+
+        // Positions where RET may return to
+        let returns: BTreeSet<usize> = script
+            .clone()
+            .filter(|&index| target(self.instructions[index]).is_some())
+            .map(|index| self.offsets[index] + self.instructions[index].size())
+            .collect();
+        let returns = if returns.is_empty() {
+            BTreeSet::from([0])
+        } else {
+            returns
+        };
+
+        // Blocks start at the beginning, on every target and after every branch
+        let mut leaders = BTreeSet::from([script.start]);
+        for index in script.clone() {
+            let instruction = self.instructions[index];
+            let mut positions = target(instruction).map(usize::from).into_iter().collect();
+            if let InstructionSet::RET(_) = instruction {
+                positions = returns.clone();
+            }
+
+            leaders.extend(
+                positions
+                    .iter()
+                    .filter_map(|&position| self.index_at(position)),
+            );
+            if branching(instruction) {
+                leaders.insert(index + 1);
+            }
+        }
+        leaders.retain(|&index| index < script.end);
+
+        let leaders: Vec<usize> = leaders.into_iter().collect();
+        for (block, &first) in leaders.iter().enumerate() {
+            let last = leaders.get(block + 1).copied().unwrap_or(script.end);
+            let end = self.offset_of(last);
+            self.blocks.push(BasicBlock {
+                offsets: self.offsets[first]..end,
+                instructions: first..last,
+                successors: vec![],
+            });
+        }
+
+        // Link every block with the blocks where the execution continues
+        for block in 0..self.blocks.len() {
+            let BasicBlock {
+                offsets,
+                instructions,
+                ..
+            } = &self.blocks[block];
+            let last = self.instructions[instructions.end - 1];
+
+            let positions: Vec<usize> = match last {
+                InstructionSet::HALT(_) => vec![],
+                InstructionSet::JMP(position) => vec![position as usize],
+                InstructionSet::RET(_) => returns.iter().copied().collect(),
+                InstructionSet::BEQ(_, _, position) | InstructionSet::BNE(_, _, position) => {
+                    vec![offsets.end, position as usize]
+                }
+                _ => vec![offsets.end],
+            };
+
+            let mut successors: Vec<Successor> = positions
+                .into_iter()
+                .filter_map(|position| self.successor_at(position))
+                .collect();
+            successors.sort();
+            successors.dedup();
+            self.blocks[block].successors = successors;
+        }
+    }
+
+    /// Index of the instruction that starts exactly at [position]
+    fn index_at(&self, position: usize) -> Option<usize> {
+        self.script
+            .clone()
+            .find(|&index| self.offsets[index] == position)
+    }
+
+    /// Byte offset of the instruction at [index], the size of the script if it is past its end
+    fn offset_of(&self, index: usize) -> usize {
+        if index < self.script.end {
+            self.offsets[index]
+        } else {
+            self.size
+        }
+    }
+
+    /// Block starting at [position], [None] if the position is in the middle of an instruction
+    fn successor_at(&self, position: usize) -> Option<Successor> {
+        if position >= self.size {
+            return Some(Successor::Exit);
+        }
+
+        self.blocks
+            .iter()
+            .position(|block| block.offsets.start == position)
+            .map(Successor::Block)
+    }
+
+    /// Blocks sorted by their byte offset
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Index of the block containing the instruction at byte offset [position]
+    pub fn block_at(&self, position: usize) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.offsets.contains(&position))
+    }
+
+    /// Whether every block can be reached from the start of the script
+    pub fn reachable(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut queue = VecDeque::new();
+        if !self.blocks.is_empty() {
+            queue.push_back(0);
+        }
+
+        while let Some(block) = queue.pop_front() {
+            if std::mem::replace(&mut reachable[block], true) {
+                continue;
+            }
+            for &successor in &self.blocks[block].successors {
+                if let Successor::Block(next) = successor {
+                    queue.push_back(next);
+                }
+            }
+        }
+
+        reachable
+    }
+
+    /// Sets of blocks that form a loop (every block can reach every other one), sorted by offset
+    pub fn loops(&self) -> Vec<Vec<usize>> {
+        // Blocks reachable from the successors of every block
+        let reach: Vec<Vec<bool>> = (0..self.blocks.len())
+            .map(|block| {
+                let mut reach = vec![false; self.blocks.len()];
+                let mut queue: VecDeque<usize> = self.next_blocks(block).collect();
+                while let Some(next) = queue.pop_front() {
+                    if !std::mem::replace(&mut reach[next], true) {
+                        queue.extend(self.next_blocks(next));
+                    }
+                }
+                reach
+            })
+            .collect();
+
+        let mut assigned = vec![false; self.blocks.len()];
+        let mut loops = vec![];
+        for block in 0..self.blocks.len() {
+            if assigned[block] || !reach[block][block] {
+                continue;
+            }
+
+            let members: Vec<usize> = (block..self.blocks.len())
+                .filter(|&other| reach[block][other] && reach[other][block])
+                .collect();
+            for &member in &members {
+                assigned[member] = true;
+            }
+            loops.push(members);
+        }
+
+        loops
+    }
+
+    /// Blocks that directly follow [block]
+    fn next_blocks(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks[block]
+            .successors
+            .iter()
+            .filter_map(|successor| match successor {
+                Successor::Block(next) => Some(*next),
+                Successor::Exit => None,
+            })
+    }
+
+    /// Report unreachable blocks, branches into the middle of an instruction and loops without
+    /// any DELAY, PAUSE or HALT, diagnostics are sorted by the index of the offending instruction
+    pub fn analyze(&self) -> Vec<Diagnostic> {
+        let mut diagnostics = vec![];
+
+        for index in self.script.clone() {
+            let Some(position) = target(self.instructions[index]) else {
+                continue;
+            };
+            if self.successor_at(position as usize).is_none() {
+                diagnostics.push(Diagnostic {
+                    index,
+                    rule: Rule::MisalignedJump(position),
+                });
+            }
+        }
+
+        let reachable = self.reachable();
+        for (block, reachable) in self.blocks.iter().zip(&reachable) {
+            if !reachable {
+                diagnostics.push(Diagnostic {
+                    index: block.instructions.start,
+                    rule: Rule::UnreachableCode,
+                });
+            }
+        }
+
+        for members in self.loops() {
+            let yielding = members.iter().any(|&block| {
+                self.blocks[block]
+                    .instructions
+                    .clone()
+                    .any(|index| yields(self.instructions[index]))
+            });
+            if !yielding && reachable[members[0]] {
+                diagnostics.push(Diagnostic {
+                    index: self.blocks[members[0]].instructions.start,
+                    rule: Rule::LoopWithoutDelay,
+                });
+            }
+        }
+
+        diagnostics.sort_by_key(|diagnostic| diagnostic.index);
+        diagnostics
+    }
+
+    /// Export the graph in Graphviz DOT format, unreachable blocks are dashed
+    pub fn to_dot(&self) -> String {
+        let reachable = self.reachable();
+        let mut dot =
+            String::from("digraph script {\n    node [shape=box, fontname=\"monospace\"];\n");
+        let mut exits = false;
+
+        for (
+            block,
+            (
+                BasicBlock {
+                    instructions,
+                    successors,
+                    ..
+                },
+                reachable,
+            ),
+        ) in self.blocks.iter().zip(&reachable).enumerate()
+        {
+            let mut label = String::new();
+            for index in instructions.clone() {
+                let instruction = self.instructions[index].to_string().replace('"', "\\\"");
+                write!(label, "{:04x}: {}\\l", self.offsets[index], instruction)
+                    .expect("writing to a String never fails");
+            }
+            let style = if *reachable { "" } else { ", style=dashed" };
+            writeln!(dot, "    b{} [label=\"{}\"{}];", block, label, style)
+                .expect("writing to a String never fails");
+
+            for successor in successors {
+                match successor {
+                    Successor::Block(next) => writeln!(dot, "    b{} -> b{};", block, next),
+                    Successor::Exit => {
+                        exits = true;
+                        writeln!(dot, "    b{} -> exit;", block)
+                    }
+                }
+                .expect("writing to a String never fails");
+            }
+        }
+
+        if exits {
+            dot.push_str("    exit [label=\"end\", shape=oval];\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler;
+
+    fn graph(source: &str) -> ControlFlowGraph {
+        ControlFlowGraph::new(&assembler::parse(source).unwrap())
+    }
+
+    fn diagnostic(index: usize, rule: Rule) -> Diagnostic {
+        Diagnostic { index, rule }
+    }
+
+    #[test]
+    fn blocks_split_on_branches_and_targets() {
+        let graph = graph("BEGIN; NOP; BEQ $0 0 6; NOP; DELAY MS 1; NOP; RUN; NOP");
+
+        assert_eq!(
+            graph.blocks(),
+            [
+                BasicBlock {
+                    offsets: 0..5,
+                    instructions: 1..3,
+                    successors: vec![Successor::Block(1), Successor::Block(2)],
+                },
+                BasicBlock {
+                    offsets: 5..6,
+                    instructions: 3..4,
+                    successors: vec![Successor::Block(2)],
+                },
+                BasicBlock {
+                    offsets: 6..10,
+                    instructions: 4..6,
+                    successors: vec![Successor::Exit],
+                },
+            ]
+        );
+        assert_eq!(graph.block_at(0), Some(0));
+        assert_eq!(graph.block_at(7), Some(2));
+        assert_eq!(graph.block_at(10), None);
+    }
+
+    #[test]
+    fn ret_returns_after_branches() {
+        let graph = graph("NOP; JMP 4; NOP; RET 0; NOP");
+
+        assert_eq!(
+            graph.blocks(),
+            [
+                BasicBlock {
+                    offsets: 0..3,
+                    instructions: 0..2,
+                    successors: vec![Successor::Block(2)],
+                },
+                BasicBlock {
+                    offsets: 3..4,
+                    instructions: 2..3,
+                    successors: vec![Successor::Block(2)],
+                },
+                BasicBlock {
+                    offsets: 4..6,
+                    instructions: 3..4,
+                    successors: vec![Successor::Block(1)],
+                },
+                BasicBlock {
+                    offsets: 6..7,
+                    instructions: 4..5,
+                    successors: vec![Successor::Exit],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn reachable_follows_successors() {
+        let graph = graph("BEGIN; JMP 3; NOP; NOP; HALT 0; NOP; RUN");
+
+        assert_eq!(graph.blocks().len(), 4);
+        assert_eq!(graph.reachable(), [true, false, true, false]);
+    }
+
+    #[test]
+    fn loops_group_blocks_that_reach_each_other() {
+        assert_eq!(
+            graph("BEGIN; NOP; BEQ $0 0 0; JMP 0; RUN").loops(),
+            [vec![0, 1]]
+        );
+        assert_eq!(
+            graph("BEGIN; LPAINT 0 5; BNE $0 0 0; NOP; DELAY MS 1; JMP 8; RUN").loops(),
+            [vec![0], vec![2]]
+        );
+        assert_eq!(
+            graph("BEGIN; NOP; JMP 3; NOP; RUN").loops(),
+            Vec::<Vec<usize>>::new()
+        );
+    }
+
+    #[test]
+    fn jump_into_an_operand() {
+        assert_eq!(
+            graph("BEGIN; LPAINT 0 5; JMP 1; RUN").analyze(),
+            [diagnostic(2, Rule::MisalignedJump(1))]
+        );
+    }
+
+    #[test]
+    fn code_after_an_unconditional_jump() {
+        assert_eq!(
+            graph("BEGIN; DELAY MS 1; JMP 0; NOP; RUN").analyze(),
+            [diagnostic(3, Rule::UnreachableCode)]
+        );
+    }
+
+    #[test]
+    fn loop_without_delay() {
+        assert_eq!(
+            graph("BEGIN; JMP 0; RUN").analyze(),
+            [diagnostic(1, Rule::LoopWithoutDelay)]
+        );
+        assert_eq!(
+            graph("BEGIN; LPAINT 0 5; BNE $0 0 0; NOP; DELAY MS 1; JMP 8; RUN").analyze(),
+            [diagnostic(1, Rule::LoopWithoutDelay)]
+        );
+
+        // Loops that are never entered are already reported as unreachable
+        assert_eq!(
+            graph("BEGIN; HALT 0; JMP 2; RUN").analyze(),
+            [diagnostic(2, Rule::UnreachableCode)]
+        );
+    }
+}
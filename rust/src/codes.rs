@@ -1,11 +1,99 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use core::str::FromStr;
+
+use crate::error::Error;
 use crate::proto;
 
 /// Designates the code for a given effect
-#[derive(Debug, Copy, Clone)]
-pub struct EffectCode(pub u8);
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum EffectCode {
+    /// Dim lights an specified amount
+    DIM,
+    /// Blend colors in a range
+    BLEND,
+    /// Effect not defined by the standard, its meaning depends on the device\
+    /// codes of standard effects are not valid custom codes (see [EffectCode::custom]) and cannot be encoded
+    Custom(u8),
+}
+
+impl EffectCode {
+    /// Create a custom effect, fails if [code] belongs to a standard effect
+    pub fn custom(code: u8) -> Result<Self, Error> {
+        match EffectCode::from(code) {
+            EffectCode::Custom(code) => Ok(EffectCode::Custom(code)),
+            _ => Err(Error::StandardEffectCode(code)),
+        }
+    }
+}
+
+impl From<proto::EffectCode> for EffectCode {
+    /// Transform a proto definition into its [EffectCode]
+    fn from(code: proto::EffectCode) -> Self {
+        match code {
+            proto::EffectCode::Dim => EffectCode::DIM,
+            proto::EffectCode::Blend => EffectCode::BLEND,
+        }
+    }
+}
+
+impl TryFrom<EffectCode> for proto::EffectCode {
+    type Error = Error;
+
+    /// Transform an [EffectCode] into its proto definition, fails for custom effects
+    fn try_from(code: EffectCode) -> Result<Self, Self::Error> {
+        match code {
+            EffectCode::DIM => Ok(proto::EffectCode::Dim),
+            EffectCode::BLEND => Ok(proto::EffectCode::Blend),
+            EffectCode::Custom(code) => Err(Error::CustomEffectCode(code)),
+        }
+    }
+}
+
+impl From<u8> for EffectCode {
+    /// Get an [EffectCode] from its binary representation
+    fn from(code: u8) -> Self {
+        proto::EffectCode::try_from(code as i32)
+            .map(EffectCode::from)
+            .unwrap_or(EffectCode::Custom(code))
+    }
+}
+
+impl From<EffectCode> for u8 {
+    /// Get an [EffectCode] binary representation based on proto definition
+    fn from(code: EffectCode) -> Self {
+        match code {
+            EffectCode::DIM => proto::EffectCode::Dim as u8,
+            EffectCode::BLEND => proto::EffectCode::Blend as u8,
+            EffectCode::Custom(code) => code,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl FromStr for EffectCode {
+    type Err = Error;
+
+    /// Get a standard [EffectCode] from its Prism Assembly Language name (DIM, BLEND)
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        proto::EffectCode::from_str_name(&name.to_ascii_uppercase())
+            .map(EffectCode::from)
+            .ok_or_else(|| Error::Syntax(format!("unknown effect '{}'", name)))
+    }
+}
+
+impl fmt::Display for EffectCode {
+    /// Prism Assembly Language name of the effect, custom effects are written as numbers
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match proto::EffectCode::try_from(*self) {
+            Ok(code) => write!(f, "{}", code.as_str_name()),
+            Err(_) => write!(f, "{}", u8::from(*self)),
+        }
+    }
+}
 
 /// Designates the code for a given delay time unit
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum DelayCode {
     /// Delay for milliseconds
     MS,
@@ -17,10 +105,10 @@ pub enum DelayCode {
     HRS,
 }
 
-impl Into<proto::DelayCode> for DelayCode {
+impl From<DelayCode> for proto::DelayCode {
     /// Transform a [DelayCode] into its proto definition
-    fn into(self) -> proto::DelayCode {
-        match self {
+    fn from(code: DelayCode) -> Self {
+        match code {
             DelayCode::MS => proto::DelayCode::Ms,
             DelayCode::SEC => proto::DelayCode::Sec,
             DelayCode::MIN => proto::DelayCode::Min,
@@ -29,10 +117,81 @@ impl Into<proto::DelayCode> for DelayCode {
     }
 }
 
-impl Into<u8> for DelayCode {
+impl From<proto::DelayCode> for DelayCode {
+    /// Transform a proto definition into its [DelayCode]
+    fn from(code: proto::DelayCode) -> Self {
+        match code {
+            proto::DelayCode::Ms => DelayCode::MS,
+            proto::DelayCode::Sec => DelayCode::SEC,
+            proto::DelayCode::Min => DelayCode::MIN,
+            proto::DelayCode::Hrs => DelayCode::HRS,
+        }
+    }
+}
+
+impl From<DelayCode> for u8 {
     /// Get a [DelayCode] binary representation based on proto definition
-    fn into(self) -> u8 {
-        let delay_code: proto::DelayCode = self.into();
+    fn from(code: DelayCode) -> Self {
+        let delay_code: proto::DelayCode = code.into();
         delay_code as u8
     }
-}
\ No newline at end of file
+}
+
+impl TryFrom<u8> for DelayCode {
+    type Error = Error;
+
+    /// Get a [DelayCode] from its binary representation
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        proto::DelayCode::try_from(code as i32)
+            .map(DelayCode::from)
+            .map_err(|_| Error::InvalidDelayCode(code))
+    }
+}
+
+impl fmt::Display for DelayCode {
+    /// Prism Assembly Language name of the time unit (MS, SEC, MIN, HRS)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", proto::DelayCode::from(*self).as_str_name())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn custom_rejects_standard_codes() {
+        assert_eq!(EffectCode::custom(0), Err(Error::StandardEffectCode(0)));
+        assert_eq!(EffectCode::custom(1), Err(Error::StandardEffectCode(1)));
+        assert_eq!(EffectCode::custom(2), Ok(EffectCode::Custom(2)));
+        assert_eq!(EffectCode::custom(255), Ok(EffectCode::Custom(255)));
+    }
+
+    #[test]
+    fn binary_representation_is_canonical() {
+        assert_eq!(EffectCode::from(0), EffectCode::DIM);
+        assert_eq!(EffectCode::from(1), EffectCode::BLEND);
+        assert_eq!(EffectCode::from(7), EffectCode::Custom(7));
+        for code in 0..=u8::MAX {
+            assert_eq!(u8::from(EffectCode::from(code)), code);
+        }
+    }
+
+    #[test]
+    fn only_standard_effects_have_a_proto_definition() {
+        assert_eq!(
+            proto::EffectCode::try_from(EffectCode::DIM),
+            Ok(proto::EffectCode::Dim)
+        );
+        assert_eq!(
+            proto::EffectCode::try_from(EffectCode::BLEND),
+            Ok(proto::EffectCode::Blend)
+        );
+        for code in [0, 1, 7] {
+            assert_eq!(
+                proto::EffectCode::try_from(EffectCode::Custom(code)),
+                Err(Error::CustomEffectCode(code))
+            );
+        }
+    }
+}
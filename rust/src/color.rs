@@ -0,0 +1,438 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::addressing::AddressingMode;
+use crate::arrays::Array3;
+use crate::error::Error;
+
+/// Color as HSL components using the protocol ranges (0-255 each)\
+/// the hue covers the whole color wheel, each step is 360/256 degrees
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Hsl {
+    pub hue: u8,
+    pub saturation: u8,
+    pub lightness: u8,
+}
+
+/// Color as RGB components (0-255 each)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+/// Color as HSV components, same ranges as [Hsl]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct Hsv {
+    pub hue: u8,
+    pub saturation: u8,
+    pub value: u8,
+}
+
+/// Shorthand for creating an [Hsl] color
+pub const fn hsl(hue: u8, saturation: u8, lightness: u8) -> Hsl {
+    Hsl {
+        hue,
+        saturation,
+        lightness,
+    }
+}
+
+/// Shorthand for creating an [Rgb] color
+pub const fn rgb(red: u8, green: u8, blue: u8) -> Rgb {
+    Rgb { red, green, blue }
+}
+
+/// Shorthand for creating an [Hsv] color
+pub const fn hsv(hue: u8, saturation: u8, value: u8) -> Hsv {
+    Hsv {
+        hue,
+        saturation,
+        value,
+    }
+}
+
+/// Colors that can be referenced by name
+const NAMED: [(&str, Hsl); 10] = [
+    ("BLACK", Hsl::BLACK),
+    ("WHITE", Hsl::WHITE),
+    ("RED", Hsl::RED),
+    ("ORANGE", Hsl::ORANGE),
+    ("YELLOW", Hsl::YELLOW),
+    ("GREEN", Hsl::GREEN),
+    ("CYAN", Hsl::CYAN),
+    ("BLUE", Hsl::BLUE),
+    ("PURPLE", Hsl::PURPLE),
+    ("MAGENTA", Hsl::MAGENTA),
+];
+
+impl Hsl {
+    /// Closest to #000000
+    pub const BLACK: Self = hsl(0, 0, 0);
+    /// Closest to #ffffff
+    pub const WHITE: Self = hsl(0, 0, 255);
+    /// Closest to #ff0000
+    pub const RED: Self = hsl(0, 255, 128);
+    /// Closest to #ff8000
+    pub const ORANGE: Self = hsl(21, 255, 128);
+    /// Closest to #ffff00
+    pub const YELLOW: Self = hsl(43, 255, 128);
+    /// Closest to #00ff00
+    pub const GREEN: Self = hsl(85, 255, 128);
+    /// Closest to #00ffff
+    pub const CYAN: Self = hsl(128, 255, 128);
+    /// Closest to #0000ff
+    pub const BLUE: Self = hsl(171, 255, 128);
+    /// Closest to #8000ff
+    pub const PURPLE: Self = hsl(192, 255, 128);
+    /// Closest to #ff00ff
+    pub const MAGENTA: Self = hsl(213, 255, 128);
+
+    /// Get a color from its name (case insensitive), see [Hsl::RED] and the other constants
+    pub fn named(name: &str) -> Option<Self> {
+        NAMED
+            .iter()
+            .find(|(named, _)| named.eq_ignore_ascii_case(name))
+            .map(|&(_, color)| color)
+    }
+
+    /// Components as immediate values, ready to be used in FILL and PAINT
+    pub fn immediate(&self) -> Array3<AddressingMode> {
+        (*self).into()
+    }
+}
+
+impl From<Hsl> for Array3<u8> {
+    /// Components in the order used by the protocol [H, S, L]
+    fn from(color: Hsl) -> Self {
+        Array3(color.hue, color.saturation, color.lightness)
+    }
+}
+
+impl From<Array3<u8>> for Hsl {
+    /// Interpret the [H, S, L] components used by the protocol
+    fn from(Array3(hue, saturation, lightness): Array3<u8>) -> Self {
+        hsl(hue, saturation, lightness)
+    }
+}
+
+impl From<Hsl> for Array3<AddressingMode> {
+    /// Components as immediate values [H, S, L]
+    fn from(color: Hsl) -> Self {
+        Array3(
+            AddressingMode::Immediate(color.hue),
+            AddressingMode::Immediate(color.saturation),
+            AddressingMode::Immediate(color.lightness),
+        )
+    }
+}
+
+impl FromStr for Hsl {
+    type Err = Error;
+
+    /// Get a color from its name (e.g. `red`) or its RGB hex code (e.g. `#ff8000`)
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        match Hsl::named(color) {
+            Some(color) => Ok(color),
+            None => Ok(color.parse::<Rgb>()?.into()),
+        }
+    }
+}
+
+impl FromStr for Rgb {
+    type Err = Error;
+
+    /// Parse an RGB hex code with or without `#`, either `rrggbb` or `rgb`
+    fn from_str(color: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::InvalidColor(color.to_string());
+        let digits = color.strip_prefix('#').unwrap_or(color);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(invalid());
+        }
+
+        let component = |digits: &str| u8::from_str_radix(digits, 16).map_err(|_| invalid());
+        match digits.len() {
+            6 => Ok(rgb(
+                component(&digits[0..2])?,
+                component(&digits[2..4])?,
+                component(&digits[4..6])?,
+            )),
+            // Every digit is repeated (#f80 is #ff8800)
+            3 => Ok(rgb(
+                component(&digits[0..1])? * 0x11,
+                component(&digits[1..2])? * 0x11,
+                component(&digits[2..3])? * 0x11,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Rgb {
+    /// RGB hex code (e.g. `#ff8000`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
+
+/// Transform a component (0-255) into a fraction (0-1)
+fn fraction(component: u8) -> f32 {
+    component as f32 / 255.0
+}
+
+/// Transform a fraction (0-1) into a component (0-255)
+fn component(fraction: f32) -> u8 {
+    (fraction.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Transform a hue (0-255) into a sector of the color wheel (0-6)
+fn sector(hue: u8) -> f32 {
+    hue as f32 / 256.0 * 6.0
+}
+
+/// Transform a sector of the color wheel (0-6) into a hue (0-255)
+fn hue(sector: f32) -> u8 {
+    ((sector / 6.0 * 256.0).round() as u32 % 256) as u8
+}
+
+/// Sector of the color wheel (0-6) of the given RGB fractions, [max] and [chroma] must be precomputed
+fn rgb_sector(r: f32, g: f32, b: f32, max: f32, chroma: f32) -> f32 {
+    if chroma == 0.0 {
+        0.0
+    } else if max == r {
+        ((g - b) / chroma).rem_euclid(6.0)
+    } else if max == g {
+        (b - r) / chroma + 2.0
+    } else {
+        (r - g) / chroma + 4.0
+    }
+}
+
+/// RGB color given a sector of the color wheel (0-6), the chroma and the amount [m] to add to every component
+fn sector_rgb(sector: f32, chroma: f32, m: f32) -> Rgb {
+    let x = chroma * (1.0 - (sector % 2.0 - 1.0).abs());
+    let (r, g, b) = match sector as u8 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+
+    rgb(component(r + m), component(g + m), component(b + m))
+}
+
+impl From<Hsl> for Rgb {
+    fn from(color: Hsl) -> Self {
+        let s = fraction(color.saturation);
+        let l = fraction(color.lightness);
+
+        let chroma = (1.0 - (2.0 * l - 1.0).abs()) * s;
+        sector_rgb(sector(color.hue), chroma, l - chroma / 2.0)
+    }
+}
+
+impl From<Rgb> for Hsl {
+    fn from(color: Rgb) -> Self {
+        let (r, g, b) = (
+            fraction(color.red),
+            fraction(color.green),
+            fraction(color.blue),
+        );
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+
+        let l = (max + min) / 2.0;
+        let s = if chroma == 0.0 {
+            0.0
+        } else {
+            chroma / (1.0 - (2.0 * l - 1.0).abs())
+        };
+
+        hsl(
+            hue(rgb_sector(r, g, b, max, chroma)),
+            component(s),
+            component(l),
+        )
+    }
+}
+
+impl From<Hsv> for Rgb {
+    fn from(color: Hsv) -> Self {
+        let s = fraction(color.saturation);
+        let v = fraction(color.value);
+
+        let chroma = v * s;
+        sector_rgb(sector(color.hue), chroma, v - chroma)
+    }
+}
+
+impl From<Rgb> for Hsv {
+    fn from(color: Rgb) -> Self {
+        let (r, g, b) = (
+            fraction(color.red),
+            fraction(color.green),
+            fraction(color.blue),
+        );
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+
+        let s = if max == 0.0 { 0.0 } else { chroma / max };
+        hsv(
+            hue(rgb_sector(r, g, b, max, chroma)),
+            component(s),
+            component(max),
+        )
+    }
+}
+
+impl From<Hsv> for Hsl {
+    /// Direct conversion, the hue is kept as is
+    fn from(color: Hsv) -> Self {
+        let s = fraction(color.saturation);
+        let v = fraction(color.value);
+
+        let l = v * (1.0 - s / 2.0);
+        let s = if l == 0.0 || l == 1.0 {
+            0.0
+        } else {
+            (v - l) / l.min(1.0 - l)
+        };
+
+        hsl(color.hue, component(s), component(l))
+    }
+}
+
+impl From<Hsl> for Hsv {
+    /// Direct conversion, the hue is kept as is
+    fn from(color: Hsl) -> Self {
+        let s = fraction(color.saturation);
+        let l = fraction(color.lightness);
+
+        let v = l + s * l.min(1.0 - l);
+        let s = if v == 0.0 { 0.0 } else { 2.0 * (1.0 - l / v) };
+
+        hsv(color.hue, component(s), component(v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Largest difference between the components of two colors
+    fn distance(a: Rgb, b: Rgb) -> u8 {
+        a.red
+            .abs_diff(b.red)
+            .max(a.green.abs_diff(b.green))
+            .max(a.blue.abs_diff(b.blue))
+    }
+
+    #[test]
+    fn named_colors_match_their_hex_code() {
+        assert_eq!(Hsl::BLACK, Hsl::from(rgb(0x00, 0x00, 0x00)));
+        assert_eq!(Hsl::WHITE, Hsl::from(rgb(0xff, 0xff, 0xff)));
+        assert_eq!(Hsl::RED, Hsl::from(rgb(0xff, 0x00, 0x00)));
+        assert_eq!(Hsl::ORANGE, Hsl::from(rgb(0xff, 0x80, 0x00)));
+        assert_eq!(Hsl::YELLOW, Hsl::from(rgb(0xff, 0xff, 0x00)));
+        assert_eq!(Hsl::GREEN, Hsl::from(rgb(0x00, 0xff, 0x00)));
+        assert_eq!(Hsl::CYAN, Hsl::from(rgb(0x00, 0xff, 0xff)));
+        assert_eq!(Hsl::BLUE, Hsl::from(rgb(0x00, 0x00, 0xff)));
+        assert_eq!(Hsl::PURPLE, Hsl::from(rgb(0x80, 0x00, 0xff)));
+        assert_eq!(Hsl::MAGENTA, Hsl::from(rgb(0xff, 0x00, 0xff)));
+    }
+
+    #[test]
+    fn names_are_case_insensitive() {
+        assert_eq!("orange".parse::<Hsl>(), Ok(Hsl::ORANGE));
+        assert_eq!("Purple".parse::<Hsl>(), Ok(Hsl::PURPLE));
+        assert_eq!("#0000ff".parse::<Hsl>(), Ok(Hsl::BLUE));
+        assert_eq!(Hsl::named("teal"), None);
+    }
+
+    #[test]
+    fn hex_codes_are_parsed() {
+        assert_eq!("#f80".parse::<Rgb>(), Ok(rgb(0xff, 0x88, 0x00)));
+        assert_eq!("#ff8000".parse::<Rgb>(), Ok(rgb(0xff, 0x80, 0x00)));
+        assert_eq!("1E90fF".parse::<Rgb>(), Ok(rgb(0x1e, 0x90, 0xff)));
+        assert_eq!(rgb(0x1e, 0x90, 0xff).to_string(), "#1e90ff");
+    }
+
+    #[test]
+    fn invalid_hex_codes_are_rejected() {
+        for color in [
+            "", "#", "#ff80", "#ff80000", "#gg0000", "#+f8", "ff 800", "#ff800٠",
+        ] {
+            assert_eq!(
+                color.parse::<Rgb>(),
+                Err(Error::InvalidColor(color.to_string())),
+                "{}",
+                color
+            );
+        }
+        assert_eq!(
+            "teal".parse::<Hsl>(),
+            Err(Error::InvalidColor("teal".to_string()))
+        );
+    }
+
+    #[test]
+    fn rgb_round_trip_is_exact_up_to_rounding() {
+        // Grays have no hue, red (0) and cyan (128) are the only primaries whose hue fits in a byte
+        for level in 0..=u8::MAX {
+            let gray = rgb(level, level, level);
+            assert!(distance(gray, Rgb::from(Hsl::from(gray))) <= 1, "{}", gray);
+            assert!(distance(gray, Rgb::from(Hsv::from(gray))) <= 1, "{}", gray);
+        }
+
+        for color in [0xff0000, 0x800000, 0x00ffff, 0x008080] {
+            let [_, r, g, b] = u32::to_be_bytes(color);
+            let color = rgb(r, g, b);
+            assert!(
+                distance(color, Rgb::from(Hsl::from(color))) <= 1,
+                "{}",
+                color
+            );
+            assert!(
+                distance(color, Rgb::from(Hsv::from(color))) <= 1,
+                "{}",
+                color
+            );
+        }
+    }
+
+    #[test]
+    fn rgb_round_trip_is_within_hue_resolution() {
+        // A byte covers 256 hues while saturated RGB colors have 1530, a hue step moves a
+        // component up to 3 units (plus 1 from rounding saturation and lightness)
+        for red in (0..=u8::MAX).step_by(5) {
+            for green in (0..=u8::MAX).step_by(3) {
+                for blue in 0..=u8::MAX {
+                    let color = rgb(red, green, blue);
+                    assert!(
+                        distance(color, Rgb::from(Hsl::from(color))) <= 4,
+                        "{}",
+                        color
+                    );
+                    assert!(
+                        distance(color, Rgb::from(Hsv::from(color))) <= 4,
+                        "{}",
+                        color
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn hsl_and_hsv_keep_the_hue() {
+        for hue in 0..=u8::MAX {
+            let color = hsl(hue, 200, 100);
+            assert_eq!(Hsl::from(Hsv::from(color)), color);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use core::fmt;
+
+use crate::instruction::InstructionSet;
+
+/// Location (1-based line and column) of a character inside Prism Assembly Language source code
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// Where an [Error] was found
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Location {
+    /// Position inside Prism Assembly Language source code
+    Source(Position),
+    /// Byte offset inside Prism Binary Format
+    Offset(usize),
+    /// Index of the instruction (or item) inside a script
+    Index(usize),
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Location::Source(position) => write!(f, "{}", position),
+            Location::Offset(offset) => write!(f, "byte {}", offset),
+            Location::Index(index) => write!(f, "instruction {}", index),
+        }
+    }
+}
+
+/// Errors produced by libnewton
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// An array was built from a collection with the wrong amount of elements
+    ArrayLength { expected: usize, found: usize },
+    /// General purpose registers only go from $0 to $31
+    RegisterOutOfRange(u8),
+    /// The memory address does not belong to any register
+    InvalidRegister(u8),
+    /// The number does not fit in a byte (0-255)
+    ByteOutOfRange(u32),
+    /// The first 6 bits of the instruction byte do not match any instruction
+    UnknownOpcode(u8),
+    /// The instruction byte marks a parameter as indirect but the instruction does not have it
+    InvalidAddressing(u8),
+    /// Elements of an array do not share the same addressing mode
+    MixedAddressing,
+    /// The effect is not defined by the standard
+    CustomEffectCode(u8),
+    /// Custom effects cannot use the code of a standard effect
+    StandardEffectCode(u8),
+    /// The byte does not match any delay code
+    InvalidDelayCode(u8),
+    /// The instruction requires more bytes than the ones available (to be decoded or encoded)
+    Truncated { expected: usize, available: usize },
+    /// Prism Assembly Language source code is not valid
+    #[cfg(feature = "std")]
+    Syntax(String),
+    /// The label is referenced but never placed
+    #[cfg(feature = "std")]
+    UndefinedLabel(String),
+    /// The label was already placed inside the same script
+    #[cfg(feature = "std")]
+    DuplicatedLabel(String),
+    /// The position of the label does not fit in a byte (0-255)
+    #[cfg(feature = "std")]
+    LabelOutOfRange(String, usize),
+    /// Only JMP, BEQ and BNE can reference labels
+    NotBranching(InstructionSet),
+    /// The script does not fit in memory
+    ScriptOverflow,
+    /// Scripts must start with BEGIN
+    ExpectedBegin,
+    /// Scripts must end with RUN
+    ExpectedRun,
+    /// BEGIN, RUN, TRANSMIT and RESET cannot be used inside a script
+    MisplacedInstruction(InstructionSet),
+    /// The text is neither a color name nor an RGB hex code
+    #[cfg(feature = "std")]
+    InvalidColor(String),
+    /// The CRC of a frame does not match its contents
+    Checksum { expected: u16, found: u16 },
+    /// The frame payload does not fit in the buffer (or in the length field)
+    FrameTooLong { length: usize, capacity: usize },
+    /// The first byte of the response does not match any response type
+    UnknownResponse(u8),
+    /// A register dump cannot hold more than 255 registers
+    TooManyRegisters(usize),
+    /// Reading from (or writing to) a device failed
+    #[cfg(feature = "std")]
+    Io(std::io::ErrorKind, String),
+    /// Error found at a given location
+    #[cfg(feature = "std")]
+    At(Location, Box<Error>),
+}
+
+impl Error {
+    /// Attach the location where the error was found
+    #[cfg(feature = "std")]
+    pub fn at(self, location: Location) -> Self {
+        Error::At(location, Box::new(self))
+    }
+
+    /// Get the location where the error was found, if known
+    pub fn location(&self) -> Option<Location> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::At(location, _) => Some(*location),
+            _ => None,
+        }
+    }
+
+    /// Get the error without its location
+    pub fn kind(&self) -> &Error {
+        match self {
+            #[cfg(feature = "std")]
+            Error::At(_, error) => error.kind(),
+            error => error,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::ArrayLength { expected, found } => {
+                write!(
+                    f,
+                    "expected an array with {} elements, found {}",
+                    expected, found
+                )
+            }
+            Error::RegisterOutOfRange(n) => {
+                write!(f, "general purpose register ${} does not exist ($0-$31)", n)
+            }
+            Error::InvalidRegister(address) => {
+                write!(
+                    f,
+                    "memory address {} does not belong to any register",
+                    address
+                )
+            }
+            Error::ByteOutOfRange(number) => {
+                write!(f, "{} does not fit in a byte (0-255)", number)
+            }
+            Error::UnknownOpcode(opcode) => write!(f, "unknown opcode {}", opcode),
+            Error::InvalidAddressing(byte) => write!(
+                f,
+                "instruction byte {:#04x} sets the addressing mode of a missing parameter",
+                byte
+            ),
+            Error::MixedAddressing => write!(
+                f,
+                "array elements must all be immediate values or all be registers"
+            ),
+            Error::CustomEffectCode(code) => write!(f, "effect {} is not a standard effect", code),
+            Error::StandardEffectCode(code) => {
+                write!(
+                    f,
+                    "effect {} is a standard effect, it cannot be custom",
+                    code
+                )
+            }
+            Error::InvalidDelayCode(code) => write!(f, "unknown delay code {}", code),
+            Error::Truncated {
+                expected,
+                available,
+            } => write!(
+                f,
+                "instruction requires {} bytes but only {} are available",
+                expected, available
+            ),
+            #[cfg(feature = "std")]
+            Error::Syntax(message) => write!(f, "{}", message),
+            #[cfg(feature = "std")]
+            Error::UndefinedLabel(label) => write!(f, "undefined label '{}'", label),
+            #[cfg(feature = "std")]
+            Error::DuplicatedLabel(label) => write!(f, "label '{}' is already defined", label),
+            #[cfg(feature = "std")]
+            Error::LabelOutOfRange(label, position) => write!(
+                f,
+                "label '{}' is at position {} which does not fit in a byte (0-255)",
+                label, position
+            ),
+            Error::NotBranching(instruction) => write!(
+                f,
+                "'{}' cannot reference a label, only JMP, BEQ and BNE can",
+                instruction
+            ),
+            Error::ScriptOverflow => write!(f, "script does not fit in memory"),
+            Error::ExpectedBegin => write!(f, "scripts must start with BEGIN"),
+            Error::ExpectedRun => write!(f, "scripts must end with RUN"),
+            Error::MisplacedInstruction(instruction) => {
+                write!(f, "'{}' cannot be used inside a script", instruction)
+            }
+            #[cfg(feature = "std")]
+            Error::InvalidColor(color) => write!(f, "invalid color '{}'", color),
+            Error::Checksum { expected, found } => write!(
+                f,
+                "frame checksum {:#06x} does not match its contents ({:#06x})",
+                found, expected
+            ),
+            Error::FrameTooLong { length, capacity } => write!(
+                f,
+                "frame payload of {} bytes exceeds the maximum of {} bytes",
+                length, capacity
+            ),
+            Error::UnknownResponse(response_type) => {
+                write!(f, "unknown response type {}", response_type)
+            }
+            Error::TooManyRegisters(count) => write!(
+                f,
+                "a register dump holds at most 255 registers, found {}",
+                count
+            ),
+            #[cfg(feature = "std")]
+            Error::Io(_, message) => write!(f, "{}", message),
+            #[cfg(feature = "std")]
+            Error::At(location, error) => write!(f, "{}: {}", location, error),
+        }
+    }
+}
+
+impl core::error::Error for Error {}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Io(error.kind(), error.to_string())
+    }
+}
@@ -0,0 +1,280 @@
+use core::fmt;
+use core::ops::{BitAnd, BitOr, BitOrAssign, Not};
+
+use crate::proto;
+
+/// Every flag in the order of its bit position
+const FLAGS: [proto::StatusFlags; 6] = [
+    proto::StatusFlags::Ht,
+    proto::StatusFlags::St,
+    proto::StatusFlags::Tx,
+    proto::StatusFlags::Ix,
+    proto::StatusFlags::Hd,
+    proto::StatusFlags::Ow,
+];
+
+/// Current operating mode of a device, given by the ST and TX flags
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Mode {
+    /// Instructions are stored and executed as a script (state machine enabled)
+    Script,
+    /// Instructions are executed as they arrive (no branching, no $PC)
+    Transmit,
+}
+
+/// Set of flags stored as a bitmask in the $SF register\
+/// bit positions are given by [proto::StatusFlags]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, Default)]
+pub struct StatusFlags(u8);
+
+impl StatusFlags {
+    /// (Halted) device is halted and the halt value is inside $SC
+    pub const HT: Self = Self::flag(proto::StatusFlags::Ht);
+    /// (Script) device is in script mode
+    pub const ST: Self = Self::flag(proto::StatusFlags::St);
+    /// (Transmit) device is in transmit mode, branching is disabled
+    pub const TX: Self = Self::flag(proto::StatusFlags::Tx);
+    /// (Indexing) absolute indexing is enabled, relative indexing otherwise
+    pub const IX: Self = Self::flag(proto::StatusFlags::Ix);
+    /// (Hold) instructions are held until an UPDATE is issued
+    pub const HD: Self = Self::flag(proto::StatusFlags::Hd);
+    /// (Overflow) an index fell outside of the buffer
+    pub const OW: Self = Self::flag(proto::StatusFlags::Ow);
+
+    /// Set containing only the given flag
+    pub const fn flag(flag: proto::StatusFlags) -> Self {
+        Self(1 << flag as u8)
+    }
+
+    /// Set without any flag
+    pub const fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Get the bitmask as stored in $SF
+    pub const fn bits(&self) -> u8 {
+        self.0
+    }
+
+    /// Whether no flag is set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether every flag in [other] is also set
+    pub const fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Set every flag in [other]
+    pub fn insert(&mut self, other: Self) {
+        self.0 |= other.0;
+    }
+
+    /// Clear every flag in [other]
+    pub fn remove(&mut self, other: Self) {
+        self.0 &= !other.0;
+    }
+
+    /// Set or clear every flag in [other] depending on [value]
+    pub fn set(&mut self, other: Self, value: bool) {
+        if value {
+            self.insert(other)
+        } else {
+            self.remove(other)
+        }
+    }
+
+    /// Iterate over every flag that is set
+    pub fn iter(&self) -> impl Iterator<Item = proto::StatusFlags> + '_ {
+        FLAGS
+            .into_iter()
+            .filter(|&flag| self.contains(Self::flag(flag)))
+    }
+
+    /// Current operating mode, [None] if neither ST nor TX are set
+    pub fn mode(&self) -> Option<Mode> {
+        if self.contains(Self::TX) {
+            Some(Mode::Transmit)
+        } else if self.contains(Self::ST) {
+            Some(Mode::Script)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the device is halted (only responds to BEGIN, RESET and TRANSMIT)
+    pub fn is_halted(&self) -> bool {
+        self.contains(Self::HT)
+    }
+
+    /// Whether instructions are held until an UPDATE is issued
+    pub fn is_holding(&self) -> bool {
+        self.contains(Self::HD)
+    }
+
+    /// Whether absolute indexing (AIDX) is used instead of relative indexing (RIDX)
+    pub fn is_absolute_indexing(&self) -> bool {
+        self.contains(Self::IX)
+    }
+
+    /// Whether an index fell outside of the buffer
+    pub fn has_overflowed(&self) -> bool {
+        self.contains(Self::OW)
+    }
+}
+
+impl From<u8> for StatusFlags {
+    /// Interpret the contents of $SF
+    fn from(bits: u8) -> Self {
+        Self(bits)
+    }
+}
+
+impl From<StatusFlags> for u8 {
+    /// Get the bitmask as stored in $SF
+    fn from(flags: StatusFlags) -> Self {
+        flags.0
+    }
+}
+
+impl From<proto::StatusFlags> for StatusFlags {
+    /// Set containing only the given flag
+    fn from(flag: proto::StatusFlags) -> Self {
+        Self::flag(flag)
+    }
+}
+
+impl BitOr for StatusFlags {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for StatusFlags {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.insert(rhs)
+    }
+}
+
+impl BitAnd for StatusFlags {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self::Output {
+        Self(self.0 & rhs.0)
+    }
+}
+
+impl Not for StatusFlags {
+    type Output = Self;
+
+    fn not(self) -> Self::Output {
+        Self(!self.0)
+    }
+}
+
+impl fmt::Display for StatusFlags {
+    /// Names of the flags that are set separated by `|` (e.g. `TX|HD`), or `NONE`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return write!(f, "NONE");
+        }
+
+        let mut first = true;
+        for flag in self.iter() {
+            if !first {
+                write!(f, "|")?;
+            }
+            write!(f, "{}", flag.as_str_name())?;
+            first = false;
+        }
+
+        // Reserved bits
+        let reserved = self.0
+            & !FLAGS
+                .iter()
+                .fold(0, |mask, &flag| mask | Self::flag(flag).0);
+        if reserved != 0 {
+            if !first {
+                write!(f, "|")?;
+            }
+            write!(f, "{:#04x}", reserved)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_match_bit_positions() {
+        assert_eq!(StatusFlags::HT.bits(), 0b000001);
+        assert_eq!(StatusFlags::ST.bits(), 0b000010);
+        assert_eq!(StatusFlags::TX.bits(), 0b000100);
+        assert_eq!(StatusFlags::IX.bits(), 0b001000);
+        assert_eq!(StatusFlags::HD.bits(), 0b010000);
+        assert_eq!(StatusFlags::OW.bits(), 0b100000);
+        assert_eq!(u8::from(StatusFlags::from(0xA5)), 0xA5);
+    }
+
+    #[test]
+    fn set_and_clear() {
+        let mut flags = StatusFlags::empty();
+        flags.insert(StatusFlags::TX | StatusFlags::HD);
+        assert!(flags.contains(StatusFlags::TX));
+        assert!(flags.is_holding());
+
+        flags.set(StatusFlags::HD, false);
+        flags.set(StatusFlags::OW, true);
+        assert_eq!(flags, StatusFlags::TX | StatusFlags::OW);
+
+        flags.remove(StatusFlags::TX | StatusFlags::OW);
+        assert!(flags.is_empty());
+    }
+
+    #[test]
+    fn display_names_every_flag() {
+        assert_eq!(StatusFlags::empty().to_string(), "NONE");
+        assert_eq!((StatusFlags::TX | StatusFlags::HD).to_string(), "TX|HD");
+        assert_eq!(StatusFlags::from(0b111111).to_string(), "HT|ST|TX|IX|HD|OW");
+
+        // Reserved bits are written in hexadecimal
+        assert_eq!(StatusFlags::from(0x44).to_string(), "TX|0x40");
+        assert_eq!(StatusFlags::from(0xC0).to_string(), "0xc0");
+    }
+
+    #[test]
+    fn mode_depends_on_st_and_tx() {
+        assert_eq!(StatusFlags::empty().mode(), None);
+        assert_eq!(StatusFlags::ST.mode(), Some(Mode::Script));
+        assert_eq!(StatusFlags::TX.mode(), Some(Mode::Transmit));
+        assert_eq!(
+            (StatusFlags::ST | StatusFlags::HT).mode(),
+            Some(Mode::Script)
+        );
+
+        // TRANSMIT takes precedence if both are set
+        assert_eq!(
+            (StatusFlags::ST | StatusFlags::TX).mode(),
+            Some(Mode::Transmit)
+        );
+    }
+
+    #[test]
+    fn iter_follows_bit_order() {
+        let flags = StatusFlags::OW | StatusFlags::HT | StatusFlags::IX;
+        assert!(flags.iter().eq([
+            proto::StatusFlags::Ht,
+            proto::StatusFlags::Ix,
+            proto::StatusFlags::Ow
+        ]));
+
+        // Reserved bits are not flags
+        assert_eq!(StatusFlags::from(0xC0).iter().count(), 0);
+    }
+}
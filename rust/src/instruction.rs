@@ -1,11 +1,21 @@
+use core::fmt;
+
 use crate::addressing::AddressingMode;
 use crate::arrays::{Array2, Array3};
 use crate::codes::{DelayCode, EffectCode};
+use crate::error::Error;
 use crate::proto;
 use crate::registers::Register;
 
+/// Maximum amount of bytes used by an instruction in Prism Binary Format (FILL)
+pub const MAX_INSTRUCTION_LENGTH: usize = 6;
+
+/// Maximum size in bytes of a script stored by a device (between BEGIN and RUN), every position
+/// (including the end) must fit in $PC
+pub const SCRIPT_SIZE: usize = u8::MAX as usize;
+
 /// Set of instructions available to Prism Assembly Language and Prism Binary Format
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum InstructionSet {
     /// (No Operation) Do nothing
     NOP,
@@ -93,6 +103,7 @@ pub enum InstructionSet {
 }
 
 /// Select either the first (A) or second (B) parameter
+#[derive(Copy, Clone)]
 enum ParameterType {
     A,
     B,
@@ -122,188 +133,451 @@ fn match_addressing(addrm: AddressingMode, mask: &mut u8, parameter_type: Parame
     }
 }
 
+/// Return the parsed address of every element in the array and alter the given [mask] like [match_addressing]\
+/// There is only one addressing bit per parameter, so every element must share the same addressing mode
+fn match_array_addressing<const N: usize>(
+    array: [AddressingMode; N],
+    mask: &mut u8,
+    parameter_type: ParameterType,
+) -> Result<[u8; N], Error> {
+    if array
+        .windows(2)
+        .any(|x| x[0].is_indirect() != x[1].is_indirect())
+    {
+        return Err(Error::MixedAddressing);
+    }
+
+    Ok(array.map(|x| match_addressing(x, mask, parameter_type)))
+}
+
+/// Bytes of a single instruction in Prism Binary Format, stored without allocating
+struct Encoded {
+    bytes: [u8; MAX_INSTRUCTION_LENGTH],
+    length: usize,
+}
+
+impl Encoded {
+    fn new() -> Self {
+        Self {
+            bytes: [0; MAX_INSTRUCTION_LENGTH],
+            length: 0,
+        }
+    }
+
+    fn push(&mut self, byte: u8) {
+        self.bytes[self.length] = byte;
+        self.length += 1;
+    }
+
+    fn append(&mut self, bytes: &[u8]) {
+        for &byte in bytes {
+            self.push(byte);
+        }
+    }
+
+    fn as_slice(&self) -> &[u8] {
+        &self.bytes[..self.length]
+    }
+}
+
+/// Assemble the instruction into Prism Binary Format\
+/// fails if any array mixes immediate and indirect addressing or if a custom effect uses a standard code
+fn encode(instruction_set: InstructionSet) -> Result<Encoded, Error> {
+    // Where to store the bytes
+    let mut result = Encoded::new();
+
+    // Get the instruction protobuf
+    let instruction: proto::InstructionSet = instruction_set.into();
+
+    // First instruction byte (with room for addressing type)
+    let mut instruction_byte = (instruction as u8) << 2;
+
+    match instruction_set {
+        // These instructions are 1 byte only
+        InstructionSet::NOP
+        | InstructionSet::BEGIN
+        | InstructionSet::RUN
+        | InstructionSet::TRANSMIT
+        | InstructionSet::AIDX
+        | InstructionSet::RIDX
+        | InstructionSet::HOLD
+        | InstructionSet::NHOLD
+        | InstructionSet::UPDATE
+        | InstructionSet::PAUSE
+        | InstructionSet::RESET => {
+            // Insert the instruction byte and nothing more
+            result.push(instruction_byte);
+        }
+
+        InstructionSet::RET(addrm) | InstructionSet::HALT(addrm) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let next_byte = match_addressing(addrm, &mut instruction_byte, ParameterType::A);
+            // Insert data
+            result.push(instruction_byte);
+            result.push(next_byte);
+        }
+
+        InstructionSet::JMP(label) => {
+            // Insert instruction
+            result.push(instruction_byte);
+            result.push(label);
+        }
+
+        InstructionSet::BEQ(a, b, label) | InstructionSet::BNE(a, b, label) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_byte = match_addressing(a, &mut instruction_byte, ParameterType::A);
+            let b_byte = match_addressing(b, &mut instruction_byte, ParameterType::B);
+
+            // Insert instruction
+            result.push(instruction_byte);
+            result.push(a_byte);
+            result.push(b_byte);
+            result.push(label);
+        }
+
+        InstructionSet::LOAD(register, value)
+        | InstructionSet::ADD(register, value)
+        | InstructionSet::SUB(register, value) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_byte = register.into();
+            let b_byte = match_addressing(value, &mut instruction_byte, ParameterType::B);
+
+            // Insert instruction
+            result.push(instruction_byte);
+            result.push(a_byte);
+            result.push(b_byte);
+        }
+
+        InstructionSet::FILL(range, paint) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_bytes =
+                match_array_addressing(range.into(), &mut instruction_byte, ParameterType::A)?;
+
+            let b_bytes =
+                match_array_addressing(paint.into(), &mut instruction_byte, ParameterType::B)?;
+
+            // Insert instruction
+            result.push(instruction_byte);
+            result.append(&a_bytes);
+            result.append(&b_bytes);
+        }
+
+        InstructionSet::HFILL(range, paint)
+        | InstructionSet::SFILL(range, paint)
+        | InstructionSet::LFILL(range, paint) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_bytes =
+                match_array_addressing(range.into(), &mut instruction_byte, ParameterType::A)?;
+
+            let b_byte = match_addressing(paint, &mut instruction_byte, ParameterType::B);
+
+            // Insert instruction
+            result.push(instruction_byte);
+            result.append(&a_bytes);
+            result.push(b_byte);
+        }
+
+        InstructionSet::PAINT(addr, paint) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_byte = match_addressing(addr, &mut instruction_byte, ParameterType::A);
+
+            // Get the array as bytes
+            let b_bytes =
+                match_array_addressing(paint.into(), &mut instruction_byte, ParameterType::B)?;
+
+            // Insert instruction
+            result.push(instruction_byte);
+            result.push(a_byte);
+            result.append(&b_bytes);
+        }
+
+        InstructionSet::HPAINT(addr, paint)
+        | InstructionSet::SPAINT(addr, paint)
+        | InstructionSet::LPAINT(addr, paint) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_byte = match_addressing(addr, &mut instruction_byte, ParameterType::A);
+            let b_byte = match_addressing(paint, &mut instruction_byte, ParameterType::B);
+
+            // Insert instruction
+            result.push(instruction_byte);
+            result.push(a_byte);
+            result.push(b_byte);
+        }
+
+        InstructionSet::EFFECT(code, range, value) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let a_bytes =
+                match_array_addressing(range.into(), &mut instruction_byte, ParameterType::A)?;
+
+            let b_byte = match_addressing(value, &mut instruction_byte, ParameterType::B);
+
+            // Insert instruction
+            result.push(instruction_byte);
+            // Insert the effect code, standard codes as custom effects would be decoded as the standard effect
+            if let EffectCode::Custom(code) = code {
+                EffectCode::custom(code)?;
+            }
+            result.push(code.into());
+            result.append(&a_bytes);
+            result.push(b_byte);
+        }
+
+        InstructionSet::DELAY(code, addrm) => {
+            // Get the addressing mode and alter the addressing mask on the instruction
+            let next_byte = match_addressing(addrm, &mut instruction_byte, ParameterType::A);
+            // Insert instruction
+            result.push(instruction_byte);
+            // Insert delay code
+            result.push(code as u8);
+            // Insert delay amount
+            result.push(next_byte);
+        }
+
+        InstructionSet::GET(register) => {
+            // Insert data
+            result.push(instruction_byte);
+            result.push(register.into());
+        }
+    }
+
+    // Return bytes
+    Ok(result)
+}
+
 /// Transform the instruction into binary format
-impl Into<Vec<u8>> for InstructionSet {
-    /// Assemble the current instructino into Prism Binary Format
-    fn into(self) -> Vec<u8> {
-        // Where to store the bytes
-        let mut result: Vec<u8> = vec![];
+#[cfg(feature = "std")]
+impl TryFrom<InstructionSet> for Vec<u8> {
+    type Error = Error;
 
-        // Get the instruction protobuf
-        let instruction: proto::InstructionSet = self.into();
+    /// Assemble the current instruction into Prism Binary Format\
+    /// fails if any array mixes immediate and indirect addressing or if a custom effect uses a standard code
+    fn try_from(instruction_set: InstructionSet) -> Result<Self, Self::Error> {
+        Ok(encode(instruction_set)?.as_slice().to_vec())
+    }
+}
 
-        // First instruction byte (with room for addressing type)
-        let mut instruction_byte = (instruction as u8) << 2;
+/// Return the total amount of bytes (instruction byte included) used by an instruction in Prism Binary Format
+pub fn instruction_length(instruction: proto::InstructionSet) -> usize {
+    match instruction {
+        proto::InstructionSet::Nop
+        | proto::InstructionSet::Begin
+        | proto::InstructionSet::Run
+        | proto::InstructionSet::Transmit
+        | proto::InstructionSet::Aidx
+        | proto::InstructionSet::Ridx
+        | proto::InstructionSet::Hold
+        | proto::InstructionSet::Nhold
+        | proto::InstructionSet::Update
+        | proto::InstructionSet::Pause
+        | proto::InstructionSet::Reset => 1,
+        proto::InstructionSet::Halt
+        | proto::InstructionSet::Jmp
+        | proto::InstructionSet::Ret
+        | proto::InstructionSet::Get => 2,
+        proto::InstructionSet::Load
+        | proto::InstructionSet::Add
+        | proto::InstructionSet::Sub
+        | proto::InstructionSet::Hpaint
+        | proto::InstructionSet::Spaint
+        | proto::InstructionSet::Lpaint
+        | proto::InstructionSet::Delay => 3,
+        proto::InstructionSet::Beq
+        | proto::InstructionSet::Bne
+        | proto::InstructionSet::Hfill
+        | proto::InstructionSet::Sfill
+        | proto::InstructionSet::Lfill => 4,
+        proto::InstructionSet::Paint | proto::InstructionSet::Effect => 5,
+        proto::InstructionSet::Fill => 6,
+    }
+}
 
+/// Return the addressing bits that can be set on the instruction byte, parameters without addressing
+/// (registers, positions and codes) do not count
+fn addressing_mask(instruction: proto::InstructionSet) -> u8 {
+    match instruction {
+        proto::InstructionSet::Nop
+        | proto::InstructionSet::Begin
+        | proto::InstructionSet::Run
+        | proto::InstructionSet::Transmit
+        | proto::InstructionSet::Aidx
+        | proto::InstructionSet::Ridx
+        | proto::InstructionSet::Hold
+        | proto::InstructionSet::Nhold
+        | proto::InstructionSet::Update
+        | proto::InstructionSet::Pause
+        | proto::InstructionSet::Reset
+        | proto::InstructionSet::Jmp
+        | proto::InstructionSet::Get => proto::AddressingMode::AbImmediate as u8,
+        proto::InstructionSet::Halt | proto::InstructionSet::Ret | proto::InstructionSet::Delay => {
+            proto::AddressingMode::AIndirect as u8
+        }
+        proto::InstructionSet::Load | proto::InstructionSet::Add | proto::InstructionSet::Sub => {
+            proto::AddressingMode::BIndirect as u8
+        }
+        proto::InstructionSet::Beq
+        | proto::InstructionSet::Bne
+        | proto::InstructionSet::Fill
+        | proto::InstructionSet::Hfill
+        | proto::InstructionSet::Sfill
+        | proto::InstructionSet::Lfill
+        | proto::InstructionSet::Paint
+        | proto::InstructionSet::Hpaint
+        | proto::InstructionSet::Spaint
+        | proto::InstructionSet::Lpaint
+        | proto::InstructionSet::Effect => proto::AddressingMode::AbIndirect as u8,
+    }
+}
+
+/// Return the addressing mode of a parameter given the [mask] of the instruction byte and the value [byte]
+fn decode_addressing(
+    byte: u8,
+    mask: u8,
+    parameter_type: ParameterType,
+) -> Result<AddressingMode, Error> {
+    let indirect = match parameter_type {
+        ParameterType::A => mask & (proto::AddressingMode::AIndirect as u8) != 0,
+        ParameterType::B => mask & (proto::AddressingMode::BIndirect as u8) != 0,
+    };
+
+    if indirect {
+        Ok(AddressingMode::Indirect(Register::try_from(byte)?))
+    } else {
+        Ok(AddressingMode::Immediate(byte))
+    }
+}
+
+impl InstructionSet {
+    /// Assemble the instruction into Prism Binary Format\
+    /// fails if any array mixes immediate and indirect addressing or if a custom effect uses a standard code
+    #[cfg(feature = "std")]
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        Vec::try_from(*self)
+    }
+
+    /// Assemble the instruction into the beginning of [buffer] without allocating\
+    /// returns the amount of bytes written, fails if any array mixes immediate and indirect addressing,
+    /// if a custom effect uses a standard code or if the buffer is smaller than [InstructionSet::size]
+    /// (at most [MAX_INSTRUCTION_LENGTH])
+    pub fn encode_into(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        let encoded = encode(*self)?;
+        let bytes = encoded.as_slice();
+
+        let available = buffer.len();
+        let destination = buffer.get_mut(..bytes.len()).ok_or(Error::Truncated {
+            expected: bytes.len(),
+            available,
+        })?;
+
+        destination.copy_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    /// Amount of bytes used by this instruction in Prism Binary Format
+    pub fn size(&self) -> usize {
+        instruction_length((*self).into())
+    }
+
+    /// Replace the target (position in the script) of a branching instruction\
+    /// returns [None] if the instruction is not JMP, BEQ or BNE
+    pub fn with_target(self, position: u8) -> Option<InstructionSet> {
         match self {
-            // These instructions are 1 byte only
-            InstructionSet::NOP
-            | InstructionSet::BEGIN
-            | InstructionSet::RUN
-            | InstructionSet::TRANSMIT
-            | InstructionSet::AIDX
-            | InstructionSet::RIDX
-            | InstructionSet::HOLD
-            | InstructionSet::NHOLD
-            | InstructionSet::UPDATE
-            | InstructionSet::PAUSE
-            | InstructionSet::RESET => {
-                // Insert the instruction byte and nothing more
-                result.push(instruction_byte);
-            }
+            InstructionSet::JMP(_) => Some(InstructionSet::JMP(position)),
+            InstructionSet::BEQ(a, b, _) => Some(InstructionSet::BEQ(a, b, position)),
+            InstructionSet::BNE(a, b, _) => Some(InstructionSet::BNE(a, b, position)),
+            _ => None,
+        }
+    }
 
-            InstructionSet::RET(addrm) | InstructionSet::HALT(addrm) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let next_byte = match_addressing(addrm, &mut instruction_byte, ParameterType::A);
-                // Insert data
-                result.push(instruction_byte);
-                result.push(next_byte);
-            }
+    /// Decode the instruction at the beginning of [bytes] (Prism Binary Format)\
+    /// returns the instruction and the amount of bytes it used, fails if the instruction byte marks
+    /// a parameter the instruction does not have as indirect
+    pub fn decode(bytes: &[u8]) -> Result<(InstructionSet, usize), Error> {
+        let instruction_byte = *bytes.first().ok_or(Error::Truncated {
+            expected: 1,
+            available: 0,
+        })?;
 
-            InstructionSet::JMP(label) => {
-                // Insert instruction
-                result.push(instruction_byte);
-                result.push(label);
-            }
+        // First 6 bits are the instruction, last 2 bits the addressing mode
+        let opcode = instruction_byte >> 2;
+        let mask = instruction_byte & (proto::AddressingMode::AbIndirect as u8);
 
-            InstructionSet::BEQ(a, b, label) | InstructionSet::BNE(a, b, label) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let a_byte = match_addressing(a, &mut instruction_byte, ParameterType::A);
-                let b_byte = match_addressing(b, &mut instruction_byte, ParameterType::B);
+        let instruction = proto::InstructionSet::try_from(opcode as i32)
+            .map_err(|_| Error::UnknownOpcode(opcode))?;
 
-                // Insert instruction
-                result.push(instruction_byte);
-                result.push(a_byte);
-                result.push(b_byte);
-                result.push(label);
-            }
+        // Addressing bits of missing parameters would be lost when decoding
+        if mask & !addressing_mask(instruction) != 0 {
+            return Err(Error::InvalidAddressing(instruction_byte));
+        }
 
-            InstructionSet::LOAD(register, value)
-            | InstructionSet::ADD(register, value)
-            | InstructionSet::SUB(register, value) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let a_byte = register.into();
-                let b_byte = match_addressing(value, &mut instruction_byte, ParameterType::B);
-
-                // Insert instruction
-                result.push(instruction_byte);
-                result.push(a_byte);
-                result.push(b_byte);
-            }
+        // Check that every operand is available
+        let length = instruction_length(instruction);
+        if bytes.len() < length {
+            return Err(Error::Truncated {
+                expected: length,
+                available: bytes.len(),
+            });
+        }
 
-            InstructionSet::FILL(range, paint) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let array: Vec<AddressingMode> = range.into();
-                let mut a_bytes: Vec<u8> = array
-                    .iter()
-                    .map(|&x| match_addressing(x, &mut instruction_byte, ParameterType::A))
-                    .collect();
-
-                let array: Vec<AddressingMode> = paint.into();
-                let mut b_bytes: Vec<u8> = array
-                    .iter()
-                    .map(|&x| match_addressing(x, &mut instruction_byte, ParameterType::B))
-                    .collect();
-
-                // Insert instruction
-                result.push(instruction_byte);
-                result.append(&mut a_bytes);
-                result.append(&mut b_bytes);
-            }
+        // Operands following the instruction byte
+        let operands = &bytes[1..length];
+        let a = |i: usize| decode_addressing(operands[i], mask, ParameterType::A);
+        let b = |i: usize| decode_addressing(operands[i], mask, ParameterType::B);
 
-            InstructionSet::HFILL(range, paint)
-            | InstructionSet::SFILL(range, paint)
-            | InstructionSet::LFILL(range, paint) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let array: Vec<AddressingMode> = range.into();
-                let mut a_bytes: Vec<u8> = array
-                    .iter()
-                    .map(|&x| match_addressing(x, &mut instruction_byte, ParameterType::A))
-                    .collect();
-
-                let b_byte = match_addressing(paint, &mut instruction_byte, ParameterType::B);
-
-                // Insert instruction
-                result.push(instruction_byte);
-                result.append(&mut a_bytes);
-                result.push(b_byte);
+        let result = match instruction {
+            proto::InstructionSet::Nop => InstructionSet::NOP,
+            proto::InstructionSet::Begin => InstructionSet::BEGIN,
+            proto::InstructionSet::Run => InstructionSet::RUN,
+            proto::InstructionSet::Transmit => InstructionSet::TRANSMIT,
+            proto::InstructionSet::Halt => InstructionSet::HALT(a(0)?),
+            proto::InstructionSet::Aidx => InstructionSet::AIDX,
+            proto::InstructionSet::Ridx => InstructionSet::RIDX,
+            proto::InstructionSet::Hold => InstructionSet::HOLD,
+            proto::InstructionSet::Nhold => InstructionSet::NHOLD,
+            proto::InstructionSet::Update => InstructionSet::UPDATE,
+            proto::InstructionSet::Jmp => InstructionSet::JMP(operands[0]),
+            proto::InstructionSet::Ret => InstructionSet::RET(a(0)?),
+            proto::InstructionSet::Beq => InstructionSet::BEQ(a(0)?, b(1)?, operands[2]),
+            proto::InstructionSet::Bne => InstructionSet::BNE(a(0)?, b(1)?, operands[2]),
+            proto::InstructionSet::Load => {
+                InstructionSet::LOAD(Register::try_from(operands[0])?, b(1)?)
             }
-
-            InstructionSet::PAINT(addr, paint) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let a_byte = match_addressing(addr, &mut instruction_byte, ParameterType::A);
-
-                // Get the array as bytes
-                let array: Vec<AddressingMode> = paint.into();
-                let mut b_bytes: Vec<u8> = array
-                    .iter()
-                    .map(|&x| match_addressing(x, &mut instruction_byte, ParameterType::B))
-                    .collect();
-
-                // Insert instruction
-                result.push(instruction_byte);
-                result.push(a_byte);
-                result.append(&mut b_bytes);
+            proto::InstructionSet::Add => {
+                InstructionSet::ADD(Register::try_from(operands[0])?, b(1)?)
             }
-
-            InstructionSet::HPAINT(addr, paint)
-            | InstructionSet::SPAINT(addr, paint)
-            | InstructionSet::LPAINT(addr, paint) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let a_byte = match_addressing(addr, &mut instruction_byte, ParameterType::A);
-                let b_byte = match_addressing(paint, &mut instruction_byte, ParameterType::B);
-
-                // Insert instruction
-                result.push(instruction_byte);
-                result.push(a_byte);
-                result.push(b_byte);
+            proto::InstructionSet::Sub => {
+                InstructionSet::SUB(Register::try_from(operands[0])?, b(1)?)
             }
-
-            InstructionSet::EFFECT(code, range, value) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let array: Vec<AddressingMode> = range.into();
-                let mut a_bytes: Vec<u8> = array
-                    .iter()
-                    .map(|&x| match_addressing(x, &mut instruction_byte, ParameterType::A))
-                    .collect();
-
-                let b_byte = match_addressing(value, &mut instruction_byte, ParameterType::B);
-
-                // Insert instruction
-                result.push(instruction_byte);
-                // Insert the effect code
-                result.push(code.0);
-                result.append(&mut a_bytes);
-                result.push(b_byte);
+            proto::InstructionSet::Fill => {
+                InstructionSet::FILL(Array2(a(0)?, a(1)?), Array3(b(2)?, b(3)?, b(4)?))
             }
-
-            InstructionSet::DELAY(code, addrm) => {
-                // Get the addressing mode and alter the addressing mask on the instruction
-                let next_byte = match_addressing(addrm, &mut instruction_byte, ParameterType::A);
-                // Insert instruction
-                result.push(instruction_byte);
-                // Insert delay code
-                result.push(code as u8);
-                // Insert delay amount
-                result.push(next_byte);
+            proto::InstructionSet::Hfill => InstructionSet::HFILL(Array2(a(0)?, a(1)?), b(2)?),
+            proto::InstructionSet::Sfill => InstructionSet::SFILL(Array2(a(0)?, a(1)?), b(2)?),
+            proto::InstructionSet::Lfill => InstructionSet::LFILL(Array2(a(0)?, a(1)?), b(2)?),
+            proto::InstructionSet::Paint => {
+                InstructionSet::PAINT(a(0)?, Array3(b(1)?, b(2)?, b(3)?))
             }
-
-            InstructionSet::GET(register) => {
-                // Insert data
-                result.push(instruction_byte);
-                result.push(register.into());
+            proto::InstructionSet::Hpaint => InstructionSet::HPAINT(a(0)?, b(1)?),
+            proto::InstructionSet::Spaint => InstructionSet::SPAINT(a(0)?, b(1)?),
+            proto::InstructionSet::Lpaint => InstructionSet::LPAINT(a(0)?, b(1)?),
+            proto::InstructionSet::Effect => {
+                InstructionSet::EFFECT(EffectCode::from(operands[0]), Array2(a(1)?, a(2)?), b(3)?)
             }
-        }
+            proto::InstructionSet::Delay => {
+                InstructionSet::DELAY(DelayCode::try_from(operands[0])?, a(1)?)
+            }
+            proto::InstructionSet::Pause => InstructionSet::PAUSE,
+            proto::InstructionSet::Get => InstructionSet::GET(Register::try_from(operands[0])?),
+            proto::InstructionSet::Reset => InstructionSet::RESET,
+        };
 
-        // Return bytes
-        result
+        Ok((result, length))
     }
 }
 
-impl Into<proto::InstructionSet> for InstructionSet {
+impl From<InstructionSet> for proto::InstructionSet {
     /// Transform [InstructionSet] into protobuf definition [proto::InstructionSet]
-    fn into(self) -> proto::InstructionSet {
-        match self {
+    fn from(instruction_set: InstructionSet) -> Self {
+        match instruction_set {
             InstructionSet::NOP => proto::InstructionSet::Nop,
             InstructionSet::BEGIN => proto::InstructionSet::Begin,
             InstructionSet::RUN => proto::InstructionSet::Run,
@@ -337,3 +611,48 @@ impl Into<proto::InstructionSet> for InstructionSet {
         }
     }
 }
+
+impl fmt::Display for InstructionSet {
+    /// Canonical Prism Assembly Language representation (e.g. `FILL [0, 100] [120, 255, 128]`)\
+    /// branching targets are written as positions in the script
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Instruction name
+        write!(f, "{}", proto::InstructionSet::from(*self).as_str_name())?;
+
+        // Operands
+        match self {
+            InstructionSet::NOP
+            | InstructionSet::BEGIN
+            | InstructionSet::RUN
+            | InstructionSet::TRANSMIT
+            | InstructionSet::AIDX
+            | InstructionSet::RIDX
+            | InstructionSet::HOLD
+            | InstructionSet::NHOLD
+            | InstructionSet::UPDATE
+            | InstructionSet::PAUSE
+            | InstructionSet::RESET => Ok(()),
+            InstructionSet::HALT(a) | InstructionSet::RET(a) => write!(f, " {}", a),
+            InstructionSet::JMP(target) => write!(f, " {}", target),
+            InstructionSet::BEQ(a, b, target) | InstructionSet::BNE(a, b, target) => {
+                write!(f, " {} {} {}", a, b, target)
+            }
+            InstructionSet::LOAD(register, value)
+            | InstructionSet::ADD(register, value)
+            | InstructionSet::SUB(register, value) => write!(f, " {} {}", register, value),
+            InstructionSet::FILL(range, color) => write!(f, " {} {}", range, color),
+            InstructionSet::HFILL(range, value)
+            | InstructionSet::SFILL(range, value)
+            | InstructionSet::LFILL(range, value) => write!(f, " {} {}", range, value),
+            InstructionSet::PAINT(index, color) => write!(f, " {} {}", index, color),
+            InstructionSet::HPAINT(index, value)
+            | InstructionSet::SPAINT(index, value)
+            | InstructionSet::LPAINT(index, value) => write!(f, " {} {}", index, value),
+            InstructionSet::EFFECT(code, range, value) => {
+                write!(f, " {} {} {}", code, range, value)
+            }
+            InstructionSet::DELAY(code, value) => write!(f, " {} {}", code, value),
+            InstructionSet::GET(register) => write!(f, " {}", register),
+        }
+    }
+}
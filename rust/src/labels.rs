@@ -0,0 +1,232 @@
+use crate::error::{Error, Location};
+use crate::instruction::InstructionSet;
+use std::collections::HashMap;
+
+/// Element of a script whose branching targets may reference labels
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Item {
+    /// Name the position of the next instruction
+    Label(String),
+    /// Instruction whose parameters are already known
+    Instruction(InstructionSet),
+    /// Branching instruction (JMP, BEQ or BNE) whose target (C parameter) is the position of a label\
+    /// the target given inside the instruction is replaced once the label is resolved
+    Branch(InstructionSet, String),
+}
+
+/// Replace every label reference with its position in the script (two passes)\
+/// Positions are byte offsets in Prism Binary Format counted from the instruction following BEGIN,
+/// which is where $PC starts when the script runs. Every BEGIN starts a new script and labels
+/// are only visible inside the script where they are placed\
+/// Errors are located at the index of the offending [Item]
+pub fn resolve(items: &[Item]) -> Result<Vec<InstructionSet>, Error> {
+    // First pass, compute the position of every label
+    let mut scopes: Vec<HashMap<&str, usize>> = vec![HashMap::new()];
+    let mut position = 0;
+
+    for (index, item) in items.iter().enumerate() {
+        match item {
+            Item::Label(label) => {
+                let scope = scopes.last_mut().expect("there is always a scope");
+                if scope.insert(label, position).is_some() {
+                    return Err(Error::DuplicatedLabel(label.clone()).at(Location::Index(index)));
+                }
+            }
+            Item::Instruction(InstructionSet::BEGIN) => {
+                // Positions restart on every script
+                scopes.push(HashMap::new());
+                position = 0;
+            }
+            Item::Instruction(instruction) | Item::Branch(instruction, _) => {
+                position += instruction.size();
+            }
+        }
+    }
+
+    // Second pass, replace the targets
+    let mut scopes = scopes.into_iter();
+    let mut scope = scopes.next().expect("there is always a scope");
+    let mut result = vec![];
+
+    for (index, item) in items.iter().enumerate() {
+        match item {
+            Item::Label(_) => {}
+            Item::Instruction(instruction) => {
+                if *instruction == InstructionSet::BEGIN {
+                    scope = scopes.next().expect("a scope was created for every BEGIN");
+                }
+                result.push(*instruction);
+            }
+            Item::Branch(instruction, label) => {
+                let error = |error: Error| error.at(Location::Index(index));
+                let &position = scope
+                    .get(label.as_str())
+                    .ok_or_else(|| error(Error::UndefinedLabel(label.clone())))?;
+                let target = u8::try_from(position)
+                    .map_err(|_| error(Error::LabelOutOfRange(label.clone(), position)))?;
+                result.push(
+                    instruction
+                        .with_target(target)
+                        .ok_or_else(|| error(Error::NotBranching(*instruction)))?,
+                );
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addressing::AddressingMode::Immediate;
+
+    fn label(name: &str) -> Item {
+        Item::Label(String::from(name))
+    }
+
+    fn jmp(name: &str) -> Item {
+        Item::Branch(InstructionSet::JMP(0), String::from(name))
+    }
+
+    fn nop() -> Item {
+        Item::Instruction(InstructionSet::NOP)
+    }
+
+    fn begin() -> Item {
+        Item::Instruction(InstructionSet::BEGIN)
+    }
+
+    fn run() -> Item {
+        Item::Instruction(InstructionSet::RUN)
+    }
+
+    #[test]
+    fn forward_and_backward_references() {
+        let beq = InstructionSet::BEQ(Immediate(1), Immediate(2), 0);
+        assert_eq!(
+            resolve(&[
+                begin(),
+                label("start"),
+                nop(),
+                jmp("end"),
+                label("end"),
+                Item::Branch(beq, String::from("start")),
+                run(),
+            ]),
+            Ok(vec![
+                InstructionSet::BEGIN,
+                InstructionSet::NOP,
+                InstructionSet::JMP(3),
+                InstructionSet::BEQ(Immediate(1), Immediate(2), 0),
+                InstructionSet::RUN,
+            ])
+        );
+    }
+
+    #[test]
+    fn duplicated_label() {
+        assert_eq!(
+            resolve(&[begin(), label("x"), nop(), label("x"), run()]),
+            Err(Error::DuplicatedLabel(String::from("x")).at(Location::Index(3)))
+        );
+    }
+
+    #[test]
+    fn undefined_label() {
+        assert_eq!(
+            resolve(&[begin(), label("x"), nop(), jmp("y"), run()]),
+            Err(Error::UndefinedLabel(String::from("y")).at(Location::Index(3)))
+        );
+    }
+
+    #[test]
+    fn label_out_of_range() {
+        let paint = || Item::Instruction(InstructionSet::LPAINT(Immediate(0), Immediate(0)));
+
+        // The last position that fits in a byte
+        let mut items = vec![begin()];
+        items.extend((0..85).map(|_| paint()));
+        items.extend([label("last"), nop(), jmp("last")]);
+        assert_eq!(resolve(&items).unwrap()[87], InstructionSet::JMP(255));
+
+        let mut items = vec![begin()];
+        items.extend((0..86).map(|_| paint()));
+        items.extend([label("far"), nop(), jmp("far")]);
+        assert_eq!(
+            resolve(&items),
+            Err(Error::LabelOutOfRange(String::from("far"), 258).at(Location::Index(89)))
+        );
+    }
+
+    #[test]
+    fn labels_are_scoped_per_script() {
+        // Same name in two scripts, positions restart on every BEGIN
+        assert_eq!(
+            resolve(&[
+                begin(),
+                label("loop"),
+                nop(),
+                jmp("loop"),
+                run(),
+                begin(),
+                nop(),
+                nop(),
+                label("loop"),
+                jmp("loop"),
+                run(),
+            ]),
+            Ok(vec![
+                InstructionSet::BEGIN,
+                InstructionSet::NOP,
+                InstructionSet::JMP(0),
+                InstructionSet::RUN,
+                InstructionSet::BEGIN,
+                InstructionSet::NOP,
+                InstructionSet::NOP,
+                InstructionSet::JMP(2),
+                InstructionSet::RUN,
+            ])
+        );
+
+        // No jumps across BEGIN, in either direction
+        assert_eq!(
+            resolve(&[
+                begin(),
+                label("first"),
+                nop(),
+                run(),
+                begin(),
+                jmp("first"),
+                run()
+            ]),
+            Err(Error::UndefinedLabel(String::from("first")).at(Location::Index(5)))
+        );
+        assert_eq!(
+            resolve(&[
+                begin(),
+                jmp("second"),
+                run(),
+                begin(),
+                label("second"),
+                run()
+            ]),
+            Err(Error::UndefinedLabel(String::from("second")).at(Location::Index(1)))
+        );
+        assert_eq!(
+            resolve(&[label("outside"), nop(), begin(), jmp("outside"), run()]),
+            Err(Error::UndefinedLabel(String::from("outside")).at(Location::Index(3)))
+        );
+    }
+
+    #[test]
+    fn not_branching() {
+        assert_eq!(
+            resolve(&[
+                label("x"),
+                Item::Branch(InstructionSet::NOP, String::from("x"))
+            ]),
+            Err(Error::NotBranching(InstructionSet::NOP).at(Location::Index(1)))
+        );
+    }
+}
@@ -1,7 +1,29 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
 pub mod instruction;
 pub mod addressing;
 pub mod arrays;
 pub mod codes;
+#[cfg(feature = "std")]
+pub mod color;
+pub mod flags;
 pub mod registers;
+#[cfg(feature = "std")]
 pub mod binary;
-pub mod proto;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod builder;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod assembler;
+#[cfg(feature = "std")]
+pub mod labels;
+#[cfg(feature = "std")]
+pub mod lint;
+#[cfg(feature = "std")]
+pub mod cfg;
+#[cfg(feature = "std")]
+pub mod machine;
+pub mod proto;
+pub mod response;
+pub mod stream;
+pub mod transport;
\ No newline at end of file
@@ -0,0 +1,487 @@
+use std::fmt;
+use std::ops::RangeInclusive;
+
+use crate::addressing::AddressingMode;
+use crate::cfg::ControlFlowGraph;
+use crate::error::Location;
+use crate::instruction::InstructionSet;
+
+/// How serious a [Diagnostic] is
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// The instructions work but probably not as intended
+    Warning,
+    /// The device ignores (or never executes) the instructions
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+/// Rules enforced by the device that can be checked before sending the instructions
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Rule {
+    /// RUN was found without a previous BEGIN, there is no script to run
+    ExpectedBegin,
+    /// The script started by this BEGIN is never terminated by RUN
+    ExpectedRun,
+    /// Branching instructions are ignored in TRANSMIT mode (outside of a script)
+    BranchingInTransmit(InstructionSet),
+    /// The instruction only works inside a script but comes before any BEGIN (e.g. PAUSE with
+    /// no script to pause), BEGIN must be the first instruction of a script
+    OutsideScript(InstructionSet),
+    /// NHOLD drops the given amount of operations held since the last UPDATE
+    DroppedOperations(usize),
+    /// Absolute indexing can overflow the buffer when its size is unknown
+    AbsoluteIndexing,
+    /// The index falls outside of the buffer (or above 100% with relative indexing)
+    IndexOverflow(u8),
+    /// No path from the start of the script reaches the instruction, see [crate::cfg]
+    UnreachableCode,
+    /// The branching target falls in the middle of a multi-byte instruction
+    MisalignedJump(u8),
+    /// The instructions loop forever without any DELAY, PAUSE or HALT
+    LoopWithoutDelay,
+}
+
+impl Rule {
+    /// Severity of breaking the rule
+    pub fn severity(&self) -> Severity {
+        match self {
+            Rule::ExpectedBegin
+            | Rule::ExpectedRun
+            | Rule::BranchingInTransmit(_)
+            | Rule::OutsideScript(_)
+            | Rule::MisalignedJump(_) => Severity::Error,
+            Rule::DroppedOperations(_)
+            | Rule::AbsoluteIndexing
+            | Rule::IndexOverflow(_)
+            | Rule::UnreachableCode
+            | Rule::LoopWithoutDelay => Severity::Warning,
+        }
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rule::ExpectedBegin => write!(f, "RUN without BEGIN, there is no script to run"),
+            Rule::ExpectedRun => write!(f, "script is never terminated by RUN"),
+            Rule::BranchingInTransmit(instruction) => write!(
+                f,
+                "'{}' is ignored in TRANSMIT mode, branching is only available inside a script",
+                instruction
+            ),
+            Rule::OutsideScript(instruction) => {
+                write!(f, "'{}' before BEGIN, there is no script yet", instruction)
+            }
+            Rule::DroppedOperations(count) => write!(
+                f,
+                "NHOLD drops {} operation(s) held since the last UPDATE",
+                count
+            ),
+            Rule::AbsoluteIndexing => {
+                write!(f, "absolute indexing can overflow a buffer of unknown size")
+            }
+            Rule::IndexOverflow(index) => write!(f, "index {} falls outside of the buffer", index),
+            Rule::UnreachableCode => write!(f, "unreachable code"),
+            Rule::MisalignedJump(position) => write!(
+                f,
+                "position {} is in the middle of an instruction",
+                position
+            ),
+            Rule::LoopWithoutDelay => write!(f, "loop without any DELAY, PAUSE or HALT"),
+        }
+    }
+}
+
+/// Broken [Rule] and the index of the offending instruction
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub index: usize,
+    pub rule: Rule,
+}
+
+impl Diagnostic {
+    /// Severity of the broken rule
+    pub fn severity(&self) -> Severity {
+        self.rule.severity()
+    }
+
+    /// Whether the device ignores (or never executes) the instruction
+    pub fn is_error(&self) -> bool {
+        self.severity() == Severity::Error
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}: {}",
+            Location::Index(self.index),
+            self.severity(),
+            self.rule
+        )
+    }
+}
+
+/// State of the device while walking the instructions
+struct Linter {
+    /// Amount of LEDs in the buffer, if known
+    leds: Option<usize>,
+    /// Index of the BEGIN of the script being received
+    script: Option<usize>,
+    /// A script was received before, RUN can resume it
+    received: bool,
+    /// IX flag
+    absolute: bool,
+    /// HD flag
+    hold: bool,
+    /// Operations waiting for an UPDATE
+    pending: usize,
+    /// Indices of the BEGIN and RUN of every script the device received
+    scripts: Vec<RangeInclusive<usize>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter {
+    fn report(&mut self, index: usize, rule: Rule) {
+        self.diagnostics.push(Diagnostic { index, rule });
+    }
+
+    /// Close the script being received (if any) without a RUN
+    fn abort_script(&mut self) {
+        if let Some(begin) = self.script.take() {
+            self.report(begin, Rule::ExpectedRun);
+        }
+    }
+
+    /// Check an index given in the current indexing mode, [end] is the exclusive end of a range
+    fn check_index(&mut self, index: usize, value: AddressingMode, end: bool) {
+        // Register contents are unknown until the instruction runs
+        let AddressingMode::Immediate(value) = value else {
+            return;
+        };
+
+        let overflow = if self.absolute {
+            match self.leds {
+                Some(leds) if end => value as usize > leds,
+                Some(leds) => value as usize >= leds,
+                None => false,
+            }
+        } else {
+            value > 100
+        };
+
+        if overflow {
+            self.report(index, Rule::IndexOverflow(value));
+        }
+    }
+
+    /// Account for an operation over the LEDs
+    fn operate(&mut self) {
+        if self.hold {
+            self.pending += 1;
+        }
+    }
+
+    fn check(&mut self, index: usize, instruction: InstructionSet) {
+        match instruction {
+            InstructionSet::BEGIN => {
+                self.abort_script();
+                self.script = Some(index);
+                self.pending = 0;
+            }
+            InstructionSet::RUN => match self.script.take() {
+                Some(begin) => {
+                    self.received = true;
+                    self.scripts.push(begin..=index);
+                }
+                None if !self.received => self.report(index, Rule::ExpectedBegin),
+                None => {}
+            },
+            InstructionSet::TRANSMIT => self.abort_script(),
+            InstructionSet::RESET => {
+                self.abort_script();
+                self.received = false;
+                self.absolute = false;
+                self.hold = false;
+                self.pending = 0;
+            }
+
+            InstructionSet::JMP(_)
+            | InstructionSet::RET(_)
+            | InstructionSet::BEQ(..)
+            | InstructionSet::BNE(..) => {
+                if self.script.is_none() {
+                    self.report(index, Rule::BranchingInTransmit(instruction));
+                }
+            }
+
+            InstructionSet::AIDX => {
+                self.absolute = true;
+                if self.leds.is_none() {
+                    self.report(index, Rule::AbsoluteIndexing);
+                }
+            }
+            InstructionSet::RIDX => self.absolute = false,
+
+            InstructionSet::HOLD => self.hold = true,
+            InstructionSet::NHOLD => {
+                if self.pending > 0 {
+                    self.report(index, Rule::DroppedOperations(self.pending));
+                }
+                self.hold = false;
+                self.pending = 0;
+            }
+            InstructionSet::UPDATE => self.pending = 0,
+
+            InstructionSet::FILL(range, _)
+            | InstructionSet::HFILL(range, _)
+            | InstructionSet::SFILL(range, _)
+            | InstructionSet::LFILL(range, _)
+            | InstructionSet::EFFECT(_, range, _) => {
+                self.check_index(index, range.0, true);
+                self.check_index(index, range.1, true);
+                self.operate();
+            }
+            InstructionSet::PAINT(led, _)
+            | InstructionSet::HPAINT(led, _)
+            | InstructionSet::SPAINT(led, _)
+            | InstructionSet::LPAINT(led, _) => {
+                self.check_index(index, led, false);
+                self.operate();
+            }
+
+            InstructionSet::PAUSE => {
+                if self.script.is_none() && !self.received {
+                    self.report(index, Rule::OutsideScript(instruction));
+                }
+            }
+
+            InstructionSet::NOP
+            | InstructionSet::HALT(_)
+            | InstructionSet::LOAD(..)
+            | InstructionSet::ADD(..)
+            | InstructionSet::SUB(..)
+            | InstructionSet::DELAY(..)
+            | InstructionSet::GET(_) => {}
+        }
+    }
+}
+
+/// Check the rules enforced by the device over instructions sent to a device that just started
+/// (TRANSMIT mode and relative indexing), [leds] is the size of its buffer if known\
+/// Instructions are walked in order, branches inside scripts are not followed but every script
+/// terminated by RUN is also checked by [ControlFlowGraph::analyze]\
+/// Diagnostics are sorted by the index of the offending instruction
+pub fn validate(instructions: &[InstructionSet], leds: Option<usize>) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        leds,
+        script: None,
+        received: false,
+        absolute: false,
+        hold: false,
+        pending: 0,
+        scripts: vec![],
+        diagnostics: vec![],
+    };
+
+    for (index, &instruction) in instructions.iter().enumerate() {
+        linter.check(index, instruction);
+    }
+    linter.abort_script();
+
+    for script in &linter.scripts {
+        let begin = *script.start();
+        let graph = ControlFlowGraph::new(&instructions[script.clone()]);
+        linter
+            .diagnostics
+            .extend(graph.analyze().into_iter().map(|diagnostic| Diagnostic {
+                index: begin + diagnostic.index,
+                ..diagnostic
+            }));
+    }
+
+    linter
+        .diagnostics
+        .sort_by_key(|diagnostic| diagnostic.index);
+    linter.diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::assembler;
+
+    fn lint(source: &str, leds: Option<usize>) -> Vec<Diagnostic> {
+        validate(&assembler::parse(source).unwrap(), leds)
+    }
+
+    fn diagnostic(index: usize, rule: Rule) -> Diagnostic {
+        Diagnostic { index, rule }
+    }
+
+    #[test]
+    fn valid_script() {
+        assert_eq!(
+            lint(
+                "RIDX; BEGIN; HOLD; FILL [0, 100] [1, 2, 3]; UPDATE; PAUSE; RUN; PAUSE; RUN",
+                None
+            ),
+            []
+        );
+    }
+
+    #[test]
+    fn run_without_begin() {
+        assert_eq!(lint("NOP; RUN", None), [diagnostic(1, Rule::ExpectedBegin)]);
+
+        // RUN resumes the last script
+        assert_eq!(lint("BEGIN; NOP; RUN; RUN", None), []);
+        assert_eq!(
+            lint("BEGIN; NOP; RUN; RESET; RUN", None),
+            [diagnostic(4, Rule::ExpectedBegin)]
+        );
+    }
+
+    #[test]
+    fn script_without_run() {
+        assert_eq!(
+            lint("BEGIN; NOP; TRANSMIT; BEGIN; NOP", None),
+            [
+                diagnostic(0, Rule::ExpectedRun),
+                diagnostic(3, Rule::ExpectedRun)
+            ]
+        );
+    }
+
+    #[test]
+    fn branching_in_transmit() {
+        assert_eq!(
+            lint(
+                "JMP 0; BEGIN; DELAY MS 10; BEQ $0 0 0; RUN; BNE $0 1 2; RET 0",
+                None
+            ),
+            [
+                diagnostic(0, Rule::BranchingInTransmit(InstructionSet::JMP(0))),
+                diagnostic(
+                    5,
+                    Rule::BranchingInTransmit(InstructionSet::BNE(
+                        AddressingMode::Indirect(crate::registers::Register::general(0).unwrap()),
+                        AddressingMode::Immediate(1),
+                        2
+                    ))
+                ),
+                diagnostic(
+                    6,
+                    Rule::BranchingInTransmit(InstructionSet::RET(AddressingMode::Immediate(0)))
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn instructions_outside_of_a_script() {
+        assert_eq!(
+            lint("PAUSE; BEGIN; PAUSE; RUN; PAUSE", None),
+            [diagnostic(0, Rule::OutsideScript(InstructionSet::PAUSE))]
+        );
+    }
+
+    #[test]
+    fn nhold_drops_operations() {
+        assert_eq!(
+            lint(
+                "HOLD; FILL [0, 100] [1, 2, 3]; LPAINT 0 5; NHOLD; HOLD; HPAINT 0 1; UPDATE; NHOLD",
+                None
+            ),
+            [diagnostic(3, Rule::DroppedOperations(2))]
+        );
+    }
+
+    #[test]
+    fn absolute_indexing_without_size() {
+        assert_eq!(
+            lint("AIDX; PAINT 200 [1, 2, 3]", None),
+            [diagnostic(0, Rule::AbsoluteIndexing)]
+        );
+        assert_eq!(lint("AIDX; PAINT 9 [1, 2, 3]", Some(10)), []);
+    }
+
+    #[test]
+    fn index_overflow() {
+        // Relative indexing goes up to 100%, register contents are unknown
+        assert_eq!(
+            lint(
+                "PAINT 101 [1, 2, 3]; HFILL [0, 100] 0; SFILL [$0, $1] 0",
+                None
+            ),
+            [diagnostic(0, Rule::IndexOverflow(101))]
+        );
+
+        // Ranges end past their last LED
+        assert_eq!(
+            lint(
+                "AIDX; LPAINT 9 0; LPAINT 10 0; LFILL [0, 10] 0; EFFECT DIM [11, 0] 0",
+                Some(10)
+            ),
+            [
+                diagnostic(2, Rule::IndexOverflow(10)),
+                diagnostic(4, Rule::IndexOverflow(11))
+            ]
+        );
+    }
+
+    #[test]
+    fn jump_into_an_operand() {
+        assert_eq!(
+            lint("NOP; BEGIN; LPAINT 0 5; JMP 1; RUN", None),
+            [diagnostic(3, Rule::MisalignedJump(1))]
+        );
+    }
+
+    #[test]
+    fn unreachable_code() {
+        assert_eq!(
+            lint("NOP; BEGIN; DELAY MS 10; JMP 0; NOP; RUN", None),
+            [diagnostic(4, Rule::UnreachableCode)]
+        );
+    }
+
+    #[test]
+    fn loop_without_delay() {
+        assert_eq!(
+            lint(
+                "BEGIN; LPAINT 0 5; JMP 0; RUN; BEGIN; DELAY MS 1; JMP 0; RUN",
+                None
+            ),
+            [diagnostic(1, Rule::LoopWithoutDelay)]
+        );
+
+        // Scripts that are never terminated by RUN are never executed
+        assert_eq!(
+            lint("BEGIN; JMP 0; TRANSMIT", None),
+            [diagnostic(0, Rule::ExpectedRun)]
+        );
+    }
+
+    #[test]
+    fn diagnostics_are_displayed_with_their_location() {
+        assert_eq!(
+            diagnostic(3, Rule::DroppedOperations(2)).to_string(),
+            "instruction 3: warning: NHOLD drops 2 operation(s) held since the last UPDATE"
+        );
+        assert_eq!(
+            diagnostic(0, Rule::OutsideScript(InstructionSet::PAUSE)).to_string(),
+            "instruction 0: error: 'PAUSE' before BEGIN, there is no script yet"
+        );
+    }
+}
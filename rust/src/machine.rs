@@ -0,0 +1,888 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::addressing::AddressingMode;
+use crate::arrays::{Array2, Array3};
+use crate::codes::{DelayCode, EffectCode};
+use crate::error::{Error, Location};
+use crate::flags::StatusFlags;
+use crate::instruction::{InstructionSet, SCRIPT_SIZE};
+use crate::proto;
+use crate::registers::{Register, GENERAL_PURPOSE_REGISTERS};
+use crate::stream::StreamDecoder;
+
+/// Amount of memory addresses, well-known registers followed by 32 general purpose registers
+const MEMORY_SIZE: usize = proto::Registers::General as usize + GENERAL_PURPOSE_REGISTERS as usize;
+
+/// Storage for the colors of a strip of LEDs, colors are stored as HSL components
+pub trait LedBuffer {
+    /// Amount of LEDs in the buffer
+    fn len(&self) -> usize;
+
+    /// Whether the buffer has no LEDs at all
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Get the color of the LED at [index]
+    fn get(&self, index: usize) -> Array3<u8>;
+
+    /// Change the color of the LED at [index]
+    fn set(&mut self, index: usize, color: Array3<u8>);
+
+    /// Called whenever the changes made to the buffer should become visible
+    fn show(&mut self) {}
+}
+
+impl LedBuffer for Vec<Array3<u8>> {
+    fn len(&self) -> usize {
+        Vec::len(self)
+    }
+
+    fn get(&self, index: usize) -> Array3<u8> {
+        self[index]
+    }
+
+    fn set(&mut self, index: usize, color: Array3<u8>) {
+        self[index] = color;
+    }
+}
+
+/// Outcome of a single execution step
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Step {
+    /// The instruction was executed
+    Executed(InstructionSet),
+    /// A DELAY was executed, the caller must wait for the given amount of time before the next step
+    Delayed(Duration),
+    /// There is nothing to execute until more bytes are received
+    Idle,
+}
+
+/// Change on the colors of a range of LEDs, can be held until an UPDATE is issued
+#[derive(Debug, Copy, Clone)]
+enum Operation {
+    /// Replace the given HSL components, [None] components are left untouched
+    Color {
+        start: usize,
+        end: usize,
+        color: [Option<u8>; 3],
+    },
+    /// Apply an effect over the range
+    Effect {
+        code: EffectCode,
+        start: usize,
+        end: usize,
+        value: u8,
+    },
+}
+
+/// Reference Newton interpreter, executes instructions in Prism Binary Format over a [LedBuffer]\
+/// Bytes are received with [Machine::feed] and executed one instruction at a time with [Machine::step]
+pub struct Machine<B: LedBuffer> {
+    /// Contents of every register
+    memory: [u8; MEMORY_SIZE],
+    /// Decodes the received bytes
+    decoder: StreamDecoder,
+    /// Instructions (or decoding errors) received and not yet executed
+    input: VecDeque<Result<InstructionSet, Error>>,
+    /// Bytes of the script between BEGIN and RUN
+    script: Vec<u8>,
+    /// Script is being received (between BEGIN and RUN)
+    recording: bool,
+    /// Script is being executed
+    running: bool,
+    /// Operations waiting for an UPDATE
+    pending: Vec<Operation>,
+    /// Register contents requested with GET
+    responses: Vec<(Register, u8)>,
+    /// LED colors
+    buffer: B,
+}
+
+impl<B: LedBuffer> Machine<B> {
+    /// Create a new machine in TRANSMIT mode (default at startup) over the given buffer
+    pub fn new(buffer: B) -> Self {
+        let mut machine = Self {
+            memory: [0; MEMORY_SIZE],
+            decoder: StreamDecoder::new(),
+            input: VecDeque::new(),
+            script: vec![],
+            recording: false,
+            running: false,
+            pending: vec![],
+            responses: vec![],
+            buffer,
+        };
+
+        machine.reset();
+        machine
+    }
+
+    /// Complete restart of all registers, flags and memory contents
+    pub fn reset(&mut self) {
+        self.memory = [0; MEMORY_SIZE];
+        self.script.clear();
+        self.recording = false;
+        self.running = false;
+        self.pending.clear();
+        self.set_flag(StatusFlags::TX, true);
+    }
+
+    /// Receive bytes in Prism Binary Format, they are executed on the following steps
+    pub fn feed(&mut self, bytes: &[u8]) {
+        self.input.extend(self.decoder.feed(bytes));
+    }
+
+    /// Execute a single instruction\
+    /// received instructions take precedence over the ones in a running script
+    pub fn step(&mut self) -> Result<Step, Error> {
+        if let Some(instruction) = self.receive()? {
+            return self.dispatch(instruction);
+        }
+
+        if self.running {
+            return self.step_script();
+        }
+
+        Ok(Step::Idle)
+    }
+
+    /// Read the contents of a register
+    pub fn read(&self, register: Register) -> u8 {
+        self.memory[u8::from(register) as usize]
+    }
+
+    /// Get the flags stored in $SF
+    pub fn status(&self) -> StatusFlags {
+        StatusFlags::from(self.read(Register::SF))
+    }
+
+    /// Whether a script is currently being executed
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    /// Whether there are received instructions waiting to be executed
+    pub fn has_input(&self) -> bool {
+        !self.input.is_empty()
+    }
+
+    /// Get the LED buffer
+    pub fn buffer(&self) -> &B {
+        &self.buffer
+    }
+
+    /// Get the LED buffer as mutable
+    pub fn buffer_mut(&mut self) -> &mut B {
+        &mut self.buffer
+    }
+
+    /// Take every register value requested with GET since the last call
+    pub fn take_responses(&mut self) -> Vec<(Register, u8)> {
+        std::mem::take(&mut self.responses)
+    }
+
+    /// Take the next received instruction, if complete
+    fn receive(&mut self) -> Result<Option<InstructionSet>, Error> {
+        self.input.pop_front().transpose()
+    }
+
+    /// Handle a received instruction depending on the current mode
+    fn dispatch(&mut self, instruction: InstructionSet) -> Result<Step, Error> {
+        match instruction {
+            InstructionSet::RESET => {
+                self.reset();
+                return Ok(Step::Executed(instruction));
+            }
+            InstructionSet::BEGIN => {
+                self.begin();
+                return Ok(Step::Executed(instruction));
+            }
+            InstructionSet::TRANSMIT => {
+                self.transmit();
+                return Ok(Step::Executed(instruction));
+            }
+            _ => {}
+        }
+
+        // Store the script until RUN is received
+        if self.recording {
+            if instruction == InstructionSet::RUN {
+                self.recording = false;
+                self.running = true;
+                self.write(Register::PC, 0);
+                return Ok(Step::Executed(instruction));
+            }
+
+            let bytes = instruction.encode()?;
+            if self.script.len() + bytes.len() > SCRIPT_SIZE {
+                self.recording = false;
+                self.script.clear();
+                return Err(Error::ScriptOverflow);
+            }
+
+            self.script.extend(bytes);
+            return Ok(Step::Executed(instruction));
+        }
+
+        // Halted devices only respond to BEGIN, RESET, TRANSMIT (and GET)
+        if self.flag(StatusFlags::HT) && !matches!(instruction, InstructionSet::GET(_)) {
+            return Ok(Step::Executed(instruction));
+        }
+
+        if instruction == InstructionSet::RUN {
+            // Resume a paused script or start it over
+            if self.flag(StatusFlags::ST) {
+                if !self.running && self.read(Register::PC) as usize >= self.script.len() {
+                    self.write(Register::PC, 0);
+                }
+                self.running = true;
+            }
+            return Ok(Step::Executed(instruction));
+        }
+
+        // Branching is only available inside a script
+        Ok(self.execute(instruction, false))
+    }
+
+    /// Execute the next instruction of the script
+    fn step_script(&mut self) -> Result<Step, Error> {
+        let position = self.read(Register::PC) as usize;
+
+        // Script finished
+        if position >= self.script.len() {
+            self.running = false;
+            return Ok(Step::Idle);
+        }
+
+        let (instruction, length) = match InstructionSet::decode(&self.script[position..]) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                // Stop the script, it cannot continue
+                self.running = false;
+                return Err(error.at(Location::Offset(position)));
+            }
+        };
+
+        // $PC points to the next instruction while executing
+        self.write(Register::PC, (position + length) as u8);
+        Ok(self.execute(instruction, true))
+    }
+
+    /// BEGIN, start receiving a new script
+    fn begin(&mut self) {
+        self.script.clear();
+        self.pending.clear();
+        self.recording = true;
+        self.running = false;
+        self.write(Register::PC, 0);
+        self.write(Register::PP, 0);
+        self.write(Register::PO, 0);
+        self.set_flag(StatusFlags::TX, false);
+        self.set_flag(StatusFlags::ST, true);
+        self.set_flag(StatusFlags::HT, false);
+    }
+
+    /// TRANSMIT, stop the state machine
+    fn transmit(&mut self) {
+        self.recording = false;
+        self.running = false;
+        self.set_flag(StatusFlags::TX, true);
+        self.set_flag(StatusFlags::ST, false);
+        self.set_flag(StatusFlags::HT, false);
+    }
+
+    /// Execute an instruction, [branching] is only enabled inside scripts
+    fn execute(&mut self, instruction: InstructionSet, branching: bool) -> Step {
+        match instruction {
+            InstructionSet::NOP
+            | InstructionSet::BEGIN
+            | InstructionSet::RUN
+            | InstructionSet::TRANSMIT
+            | InstructionSet::RESET => {}
+
+            InstructionSet::HALT(value) => {
+                let value = self.value(value);
+                self.write(Register::SC, value);
+                self.set_flag(StatusFlags::HT, true);
+                self.running = false;
+            }
+
+            InstructionSet::AIDX => self.set_flag(StatusFlags::IX, true),
+            InstructionSet::RIDX => self.set_flag(StatusFlags::IX, false),
+
+            InstructionSet::HOLD => self.set_flag(StatusFlags::HD, true),
+            InstructionSet::NHOLD => {
+                // Pending operations are dropped
+                self.pending.clear();
+                self.write(Register::PO, 0);
+                self.set_flag(StatusFlags::HD, false);
+            }
+            InstructionSet::UPDATE => {
+                for operation in std::mem::take(&mut self.pending) {
+                    self.apply(operation);
+                }
+                self.write(Register::PO, 0);
+                self.buffer.show();
+            }
+
+            InstructionSet::JMP(position) => {
+                if branching {
+                    self.jump(position);
+                }
+            }
+            InstructionSet::RET(value) => {
+                if branching {
+                    let value = self.value(value);
+                    self.write(Register::PC, self.read(Register::PP));
+                    self.write(Register::RV, value);
+                }
+            }
+            InstructionSet::BEQ(a, b, position) => {
+                if branching && self.value(a) == self.value(b) {
+                    self.jump(position);
+                }
+            }
+            InstructionSet::BNE(a, b, position) => {
+                if branching && self.value(a) != self.value(b) {
+                    self.jump(position);
+                }
+            }
+
+            InstructionSet::LOAD(register, value) => {
+                let value = self.value(value);
+                self.write(register, value);
+            }
+            InstructionSet::ADD(register, value) => {
+                let value = self.read(register).wrapping_add(self.value(value));
+                self.write(register, value);
+            }
+            InstructionSet::SUB(register, value) => {
+                let value = self.read(register).wrapping_sub(self.value(value));
+                self.write(register, value);
+            }
+
+            InstructionSet::FILL(range, color) => {
+                let color = [
+                    Some(self.value(color.0)),
+                    Some(self.value(color.1)),
+                    Some(self.value(color.2)),
+                ];
+                self.color_range(range, color);
+            }
+            InstructionSet::HFILL(range, value) => {
+                let color = [Some(self.value(value)), None, None];
+                self.color_range(range, color);
+            }
+            InstructionSet::SFILL(range, value) => {
+                let color = [None, Some(self.value(value)), None];
+                self.color_range(range, color);
+            }
+            InstructionSet::LFILL(range, value) => {
+                let color = [None, None, Some(self.value(value))];
+                self.color_range(range, color);
+            }
+
+            InstructionSet::PAINT(index, color) => {
+                let color = [
+                    Some(self.value(color.0)),
+                    Some(self.value(color.1)),
+                    Some(self.value(color.2)),
+                ];
+                self.color_single(index, color);
+            }
+            InstructionSet::HPAINT(index, value) => {
+                let color = [Some(self.value(value)), None, None];
+                self.color_single(index, color);
+            }
+            InstructionSet::SPAINT(index, value) => {
+                let color = [None, Some(self.value(value)), None];
+                self.color_single(index, color);
+            }
+            InstructionSet::LPAINT(index, value) => {
+                let color = [None, None, Some(self.value(value))];
+                self.color_single(index, color);
+            }
+
+            InstructionSet::EFFECT(code, range, value) => {
+                let (start, end) = self.select_range(range);
+                let value = self.value(value);
+                self.operate(Operation::Effect {
+                    code,
+                    start,
+                    end,
+                    value,
+                });
+            }
+
+            InstructionSet::DELAY(code, amount) => {
+                let amount = self.value(amount) as u64;
+                return Step::Delayed(match code {
+                    DelayCode::MS => Duration::from_millis(amount),
+                    DelayCode::SEC => Duration::from_secs(amount),
+                    DelayCode::MIN => Duration::from_secs(amount * 60),
+                    DelayCode::HRS => Duration::from_secs(amount * 60 * 60),
+                });
+            }
+
+            InstructionSet::PAUSE => self.running = false,
+
+            InstructionSet::GET(register) => {
+                let value = self.read(register);
+                self.responses.push((register, value));
+            }
+        }
+
+        Step::Executed(instruction)
+    }
+
+    /// Copy $PC into $PP and then set $PC to [position]
+    fn jump(&mut self, position: u8) {
+        self.write(Register::PP, self.read(Register::PC));
+        self.write(Register::PC, position);
+    }
+
+    /// Write the contents of a register
+    fn write(&mut self, register: Register, value: u8) {
+        self.memory[u8::from(register) as usize] = value;
+    }
+
+    /// Resolve the value of a parameter depending on its addressing mode
+    fn value(&self, addressing: AddressingMode) -> u8 {
+        match addressing {
+            AddressingMode::Immediate(value) => value,
+            AddressingMode::Indirect(register) => self.read(register),
+        }
+    }
+
+    /// Get the value of a status flag from $SF
+    fn flag(&self, flag: StatusFlags) -> bool {
+        self.status().contains(flag)
+    }
+
+    /// Change the value of a status flag in $SF
+    fn set_flag(&mut self, flag: StatusFlags, value: bool) {
+        let mut flags = self.status();
+        flags.set(flag, value);
+        self.write(Register::SF, flags.into());
+    }
+
+    /// Transform an index given in the current indexing mode into an absolute index\
+    /// sets the OW flag when the index falls outside of the buffer
+    fn index(&mut self, index: u8) -> usize {
+        let length = self.buffer.len();
+        let index = if self.flag(StatusFlags::IX) {
+            index as usize
+        } else {
+            // Relative indexing maps 0-100% into the buffer size
+            if index > 100 {
+                self.set_flag(StatusFlags::OW, true);
+            }
+            (index.min(100) as usize * length) / 100
+        };
+
+        if index > length {
+            self.set_flag(StatusFlags::OW, true);
+        }
+
+        index.min(length)
+    }
+
+    /// Get the absolute [start, end) of a range and store it in $R0 and $R1
+    fn select_range(&mut self, range: Array2<AddressingMode>) -> (usize, usize) {
+        let start = self.value(range.0);
+        let end = self.value(range.1);
+        let start = self.index(start);
+        let end = self.index(end).max(start);
+
+        self.write(Register::R0, start.min(u8::MAX as usize) as u8);
+        self.write(Register::R1, end.min(u8::MAX as usize) as u8);
+        (start, end)
+    }
+
+    /// Change the color of a range of LEDs
+    fn color_range(&mut self, range: Array2<AddressingMode>, color: [Option<u8>; 3]) {
+        let (start, end) = self.select_range(range);
+        self.operate(Operation::Color { start, end, color });
+    }
+
+    /// Change the color of a single LED
+    fn color_single(&mut self, index: AddressingMode, color: [Option<u8>; 3]) {
+        let index = self.value(index);
+        let length = self.buffer.len();
+        let mut start = self.index(index);
+
+        // The last position (100% or the buffer size) refers to the last LED
+        if start == length && length > 0 {
+            if self.flag(StatusFlags::IX) {
+                self.set_flag(StatusFlags::OW, true);
+            }
+            start = length - 1;
+        }
+
+        let end = (start + 1).min(length);
+        self.write(Register::R0, start.min(u8::MAX as usize) as u8);
+        self.write(Register::R1, end.min(u8::MAX as usize) as u8);
+        self.operate(Operation::Color { start, end, color });
+    }
+
+    /// Apply an operation right away or keep it until UPDATE if HOLD is enabled
+    fn operate(&mut self, operation: Operation) {
+        if self.flag(StatusFlags::HD) {
+            self.pending.push(operation);
+            self.write(Register::PO, self.pending.len().min(u8::MAX as usize) as u8);
+        } else {
+            self.apply(operation);
+            self.buffer.show();
+        }
+    }
+
+    /// Apply an operation over the buffer
+    fn apply(&mut self, operation: Operation) {
+        match operation {
+            Operation::Color { start, end, color } => {
+                for index in start..end {
+                    let current = self.buffer.get(index);
+                    self.buffer.set(
+                        index,
+                        Array3(
+                            color[0].unwrap_or(current.0),
+                            color[1].unwrap_or(current.1),
+                            color[2].unwrap_or(current.2),
+                        ),
+                    );
+                }
+            }
+
+            Operation::Effect {
+                code,
+                start,
+                end,
+                value,
+            } => {
+                if start >= end {
+                    return;
+                }
+
+                match code {
+                    // Reduce the level of every LED
+                    EffectCode::DIM => {
+                        for index in start..end {
+                            let Array3(h, s, l) = self.buffer.get(index);
+                            self.buffer
+                                .set(index, Array3(h, s, l.saturating_sub(value)));
+                        }
+                    }
+                    // Blend towards a gradient between the first and the last LED of the range
+                    EffectCode::BLEND => {
+                        let first = self.buffer.get(start);
+                        let last = self.buffer.get(end - 1);
+                        let steps = (end - start - 1).max(1) as i32;
+
+                        for (step, index) in (start..end).enumerate() {
+                            let current = self.buffer.get(index);
+                            let mix = |from: u8, to: u8, current: u8| {
+                                let target =
+                                    from as i32 + (to as i32 - from as i32) * step as i32 / steps;
+                                (current as i32
+                                    + (target - current as i32) * value as i32 / u8::MAX as i32)
+                                    as u8
+                            };
+
+                            self.buffer.set(
+                                index,
+                                Array3(
+                                    mix(first.0, last.0, current.0),
+                                    mix(first.1, last.1, current.1),
+                                    mix(first.2, last.2, current.2),
+                                ),
+                            );
+                        }
+                    }
+                    // Custom effects are not supported by the reference interpreter
+                    EffectCode::Custom(_) => {}
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::binary;
+
+    const BLACK: Array3<u8> = Array3(0, 0, 0);
+
+    fn with_leds(leds: usize) -> Machine<Vec<Array3<u8>>> {
+        Machine::new(vec![BLACK; leds])
+    }
+
+    /// Feed Prism Assembly Language and step until there is nothing left to execute
+    fn run(machine: &mut Machine<Vec<Array3<u8>>>, source: &str) -> Vec<Step> {
+        machine.feed(&binary::assemble_str(source).unwrap());
+
+        let mut steps = vec![];
+        for _ in 0..1000 {
+            match machine.step().unwrap() {
+                Step::Idle => return steps,
+                step => steps.push(step),
+            }
+        }
+        panic!("machine did not stop");
+    }
+
+    fn gp(n: u8) -> Register {
+        Register::general(n).unwrap()
+    }
+
+    #[test]
+    fn script_is_recorded_until_run() {
+        let mut machine = with_leds(4);
+        run(&mut machine, "BEGIN; FILL [0, 100] [1, 2, 3]");
+        assert_eq!(machine.buffer(), &vec![BLACK; 4]);
+        assert!(!machine.is_running());
+        assert_eq!(machine.status(), StatusFlags::ST);
+
+        run(&mut machine, "RUN");
+        assert_eq!(machine.buffer(), &vec![Array3(1, 2, 3); 4]);
+        assert_eq!(machine.read(Register::PC), 6);
+
+        // RUN starts a finished script over
+        machine.buffer_mut().fill(BLACK);
+        run(&mut machine, "RUN");
+        assert_eq!(machine.buffer(), &vec![Array3(1, 2, 3); 4]);
+    }
+
+    #[test]
+    fn script_overflow_drops_the_script() {
+        let mut machine = with_leds(4);
+        machine.feed(&binary::assemble_str("BEGIN").unwrap());
+        machine.step().unwrap();
+
+        // PAINT uses 5 bytes, 51 of them fill the script memory
+        let paint = binary::assemble_str("PAINT 0 [1, 2, 3]").unwrap();
+        for _ in 0..SCRIPT_SIZE / paint.len() {
+            machine.feed(&paint);
+            assert!(machine.step().is_ok());
+        }
+
+        machine.feed(&paint);
+        assert_eq!(machine.step(), Err(Error::ScriptOverflow));
+
+        // Following instructions are no longer recorded, RUN has nothing to execute
+        run(&mut machine, "RUN");
+        assert!(!machine.is_running());
+        assert_eq!(machine.buffer(), &vec![BLACK; 4]);
+    }
+
+    #[test]
+    fn hold_keeps_operations_until_update() {
+        let mut machine = with_leds(4);
+        run(
+            &mut machine,
+            "HOLD; FILL [0, 100] [1, 2, 3]; PAINT 0 [4, 5, 6]",
+        );
+        assert_eq!(machine.buffer(), &vec![BLACK; 4]);
+        assert_eq!(machine.read(Register::PO), 2);
+
+        run(&mut machine, "UPDATE");
+        assert_eq!(
+            machine.buffer(),
+            &vec![
+                Array3(4, 5, 6),
+                Array3(1, 2, 3),
+                Array3(1, 2, 3),
+                Array3(1, 2, 3)
+            ]
+        );
+        assert_eq!(machine.read(Register::PO), 0);
+    }
+
+    #[test]
+    fn nhold_drops_pending_operations() {
+        let mut machine = with_leds(4);
+        run(&mut machine, "HOLD; FILL [0, 100] [1, 2, 3]; NHOLD; UPDATE");
+        assert_eq!(machine.buffer(), &vec![BLACK; 4]);
+        assert_eq!(machine.read(Register::PO), 0);
+        assert!(!machine.status().contains(StatusFlags::HD));
+
+        // Operations are immediate again
+        run(&mut machine, "LPAINT 0 9");
+        assert_eq!(machine.buffer()[0], Array3(0, 0, 9));
+    }
+
+    #[test]
+    fn relative_indexing_maps_percentages() {
+        let mut machine = with_leds(10);
+        run(&mut machine, "RIDX; FILL [0, 50] [1, 2, 3]");
+        assert_eq!(machine.buffer()[..5], [Array3(1, 2, 3); 5]);
+        assert_eq!(machine.buffer()[5..], [BLACK; 5]);
+        assert_eq!(
+            (machine.read(Register::R0), machine.read(Register::R1)),
+            (0, 5)
+        );
+        assert!(!machine.status().contains(StatusFlags::OW));
+
+        // 100% is the last LED, anything above overflows
+        run(&mut machine, "HPAINT 100 7");
+        assert_eq!(machine.buffer()[9], Array3(7, 0, 0));
+        assert!(!machine.status().contains(StatusFlags::OW));
+
+        run(&mut machine, "HFILL [90, 200] 8");
+        assert_eq!(machine.buffer()[9], Array3(8, 0, 0));
+        assert!(machine.status().contains(StatusFlags::OW));
+    }
+
+    #[test]
+    fn absolute_indexing_sets_overflow() {
+        let mut machine = with_leds(10);
+        run(&mut machine, "AIDX; FILL [2, 4] [1, 2, 3]");
+        assert_eq!(
+            machine.buffer()[1..5],
+            [BLACK, Array3(1, 2, 3), Array3(1, 2, 3), BLACK]
+        );
+        assert_eq!(
+            (machine.read(Register::R0), machine.read(Register::R1)),
+            (2, 4)
+        );
+        assert!(!machine.status().contains(StatusFlags::OW));
+
+        // Ranges are clamped to the buffer
+        run(&mut machine, "SFILL [8, 20] 5");
+        assert_eq!(
+            machine.buffer()[7..],
+            [BLACK, Array3(0, 5, 0), Array3(0, 5, 0)]
+        );
+        assert_eq!(
+            (machine.read(Register::R0), machine.read(Register::R1)),
+            (8, 10)
+        );
+        assert!(machine.status().contains(StatusFlags::OW));
+
+        // Single LEDs past the end paint the last one
+        let mut machine = with_leds(10);
+        run(&mut machine, "AIDX; LPAINT 10 6");
+        assert_eq!(machine.buffer()[9], Array3(0, 0, 6));
+        assert!(machine.status().contains(StatusFlags::OW));
+    }
+
+    #[test]
+    fn dim_reduces_the_level() {
+        let mut machine = with_leds(4);
+        run(
+            &mut machine,
+            "FILL [0, 100] [10, 20, 100]; EFFECT DIM [0, 50] 30; EFFECT DIM [50, 100] 255",
+        );
+        assert_eq!(
+            machine.buffer(),
+            &vec![
+                Array3(10, 20, 70),
+                Array3(10, 20, 70),
+                Array3(10, 20, 0),
+                Array3(10, 20, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn blend_makes_a_gradient_between_the_ends() {
+        let mut machine = with_leds(5);
+        run(
+            &mut machine,
+            "PAINT 100 [200, 100, 40]; EFFECT BLEND [0, 100] 255",
+        );
+        assert_eq!(
+            machine.buffer(),
+            &vec![
+                Array3(0, 0, 0),
+                Array3(50, 25, 10),
+                Array3(100, 50, 20),
+                Array3(150, 75, 30),
+                Array3(200, 100, 40),
+            ]
+        );
+
+        // Half way towards the gradient
+        let mut machine = with_leds(3);
+        run(
+            &mut machine,
+            "PAINT 100 [200, 200, 200]; PAINT 50 [200, 200, 200]; EFFECT BLEND [0, 100] 128",
+        );
+        assert_eq!(machine.buffer()[1], Array3(150, 150, 150));
+    }
+
+    #[test]
+    fn delay_reports_the_duration() {
+        let mut machine = with_leds(1);
+        assert_eq!(
+            run(&mut machine, "DELAY MS 200"),
+            [Step::Delayed(Duration::from_millis(200))]
+        );
+        assert_eq!(
+            run(&mut machine, "LOAD $0 3; DELAY MIN $0")[1],
+            Step::Delayed(Duration::from_secs(180))
+        );
+
+        // Scripts continue after the delay on the next step
+        let steps = run(&mut machine, "BEGIN; DELAY HRS 1; LPAINT 0 9; RUN");
+        assert!(steps.contains(&Step::Delayed(Duration::from_secs(3600))));
+        assert_eq!(
+            steps.last(),
+            Some(&Step::Executed(InstructionSet::LPAINT(
+                AddressingMode::Immediate(0),
+                AddressingMode::Immediate(9)
+            )))
+        );
+        assert_eq!(machine.buffer()[0], Array3(0, 0, 9));
+    }
+
+    #[test]
+    fn bne_loops_until_equal() {
+        let mut machine = with_leds(1);
+        run(
+            &mut machine,
+            "BEGIN; LOAD $0 0; loop: ADD $0 1; BNE $0 5 loop; SUB $0 2; RUN",
+        );
+        assert_eq!(machine.read(gp(0)), 3);
+        assert!(!machine.is_running());
+    }
+
+    #[test]
+    fn beq_skips_instructions() {
+        let mut machine = with_leds(1);
+        run(
+            &mut machine,
+            "BEGIN; BEQ $0 0 skip; LOAD $1 1; skip: LOAD $2 2; BEQ $2 0 end; LOAD $3 3; end: RUN",
+        );
+        assert_eq!(machine.read(gp(1)), 0);
+        assert_eq!(machine.read(gp(2)), 2);
+        assert_eq!(machine.read(gp(3)), 3);
+    }
+
+    #[test]
+    fn ret_returns_after_jmp() {
+        let mut machine = with_leds(1);
+        run(
+            &mut machine,
+            "BEGIN; JMP function; LOAD $0 $RV; HALT 42; function: RET 7; RUN",
+        );
+        assert_eq!(machine.read(Register::RV), 7);
+        assert_eq!(machine.read(gp(0)), 7);
+        assert_eq!(machine.read(Register::PP), 2);
+        assert_eq!(machine.read(Register::SC), 42);
+        assert!(machine.status().contains(StatusFlags::HT));
+        assert!(!machine.is_running());
+    }
+
+    #[test]
+    fn branching_is_disabled_in_transmit() {
+        let mut machine = with_leds(1);
+        run(&mut machine, "LOAD $PC 3; JMP 10; BEQ 0 0 20; RET 7");
+        assert_eq!(machine.read(Register::PC), 3);
+        assert_eq!(machine.read(Register::PP), 0);
+        assert_eq!(machine.read(Register::RV), 0);
+        assert!(machine.status().contains(StatusFlags::TX));
+    }
+}
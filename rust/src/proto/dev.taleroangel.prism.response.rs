@@ -0,0 +1,54 @@
+// @generated
+// This file is @generated by prost-build.
+/// Contents of a single register
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterValue {
+    /// Memory address of the register (see memory.Registers)
+    #[prost(uint32, tag="1")]
+    pub address: u32,
+    /// Contents of the register (8 bits)
+    #[prost(uint32, tag="2")]
+    pub value: u32,
+}
+/// Contents of multiple registers, protobuf representation of any response
+/// A REGISTER response is a dump with a single register
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RegisterDump {
+    #[prost(message, repeated, tag="1")]
+    pub registers: ::prost::alloc::vec::Vec<RegisterValue>,
+}
+/// Type of the reply sent by a device when GET is executed, it is the first byte of the response
+/// Responses are sent in Prism Response Format (type byte followed by address and value pairs)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum ResponseType {
+    /// Contents of a single register
+    /// \[REGISTER, address, value\]
+    Register = 0,
+    /// Contents of multiple registers
+    /// \[DUMP, count, address, value, address, value, ...\]
+    Dump = 1,
+}
+impl ResponseType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            ResponseType::Register => "REGISTER",
+            ResponseType::Dump => "DUMP",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "REGISTER" => Some(Self::Register),
+            "DUMP" => Some(Self::Dump),
+            _ => None,
+        }
+    }
+}
+// @@protoc_insertion_point(module)
@@ -2,4 +2,5 @@
 
 include!("dev.taleroangel.prism.codes.rs");
 include!("dev.taleroangel.prism.instructions.rs");
-include!("dev.taleroangel.prism.memory.rs");
\ No newline at end of file
+include!("dev.taleroangel.prism.memory.rs");
+include!("dev.taleroangel.prism.response.rs");
\ No newline at end of file
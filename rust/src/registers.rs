@@ -1,7 +1,49 @@
+use core::fmt;
+
+use crate::error::Error;
 use crate::proto;
 
+/// Amount of general purpose registers available ($0-$31)
+pub const GENERAL_PURPOSE_REGISTERS: u8 = 32;
+
+/// Number of a general purpose register, guaranteed to be in the range $0-$31
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct GeneralPurpose(u8);
+
+impl GeneralPurpose {
+    /// Create a reference to the general purpose register $[n], fails if it is not in the range $0-$31
+    pub fn new(n: u8) -> Result<Self, Error> {
+        if n < GENERAL_PURPOSE_REGISTERS {
+            Ok(Self(n))
+        } else {
+            Err(Error::RegisterOutOfRange(n))
+        }
+    }
+
+    /// Get the number of the register (without the GENERAL offset)
+    pub fn number(&self) -> u8 {
+        self.0
+    }
+}
+
+impl TryFrom<u8> for GeneralPurpose {
+    type Error = Error;
+
+    /// Same as [GeneralPurpose::new]
+    fn try_from(n: u8) -> Result<Self, Self::Error> {
+        Self::new(n)
+    }
+}
+
+impl From<GeneralPurpose> for u8 {
+    /// Get the number of the register (without the GENERAL offset)
+    fn from(register: GeneralPurpose) -> Self {
+        register.0
+    }
+}
+
 /// Memory registers for keeping execution state
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Register {
     /// (Status Code) Current execution status code. 0 for success
     SC,
@@ -21,14 +63,21 @@ pub enum Register {
     /// Current amount of pending operations
     PO,
     /// General purpose registers from $0-$31
-    GeneralPurpose(u8),
+    GeneralPurpose(GeneralPurpose),
+}
+
+impl Register {
+    /// Reference the general purpose register $[n], fails if it is not in the range $0-$31
+    pub fn general(n: u8) -> Result<Self, Error> {
+        Ok(Register::GeneralPurpose(GeneralPurpose::new(n)?))
+    }
 }
 
-impl Into<u8> for Register {
+impl From<Register> for u8 {
     /// Get the absolute memory address of the register from the protobuf definition\
     /// General Purpose Registers have an offset, which is defined as GENERAL
-    fn into(self) -> u8 {
-        match self {
+    fn from(register: Register) -> Self {
+        match register {
             Register::SC => proto::Registers::Sc as u8,
             Register::SF => proto::Registers::Sf as u8,
             Register::PC => proto::Registers::Pc as u8,
@@ -37,7 +86,54 @@ impl Into<u8> for Register {
             Register::R0 => proto::Registers::R0 as u8,
             Register::R1 => proto::Registers::R1 as u8,
             Register::PO => proto::Registers::Po as u8,
-            Register::GeneralPurpose(n) => (proto::Registers::General as u8) + n,
+            Register::GeneralPurpose(n) => (proto::Registers::General as u8) + n.number(),
         }
     }
 }
+
+impl TryFrom<u8> for Register {
+    type Error = Error;
+
+    /// Get the register stored at the given absolute memory address
+    fn try_from(address: u8) -> Result<Self, Self::Error> {
+        let general = proto::Registers::General as u8;
+        if address >= general {
+            // General purpose registers go from $0 to $31
+            return Register::general(address - general)
+                .map_err(|_| Error::InvalidRegister(address));
+        }
+
+        let register = proto::Registers::try_from(address as i32)
+            .map_err(|_| Error::InvalidRegister(address))?;
+
+        match register {
+            proto::Registers::Sc => Ok(Register::SC),
+            proto::Registers::Sf => Ok(Register::SF),
+            proto::Registers::Pc => Ok(Register::PC),
+            proto::Registers::Pp => Ok(Register::PP),
+            proto::Registers::Rv => Ok(Register::RV),
+            proto::Registers::R0 => Ok(Register::R0),
+            proto::Registers::R1 => Ok(Register::R1),
+            proto::Registers::Po => Ok(Register::PO),
+            proto::Registers::General => Err(Error::InvalidRegister(address)),
+        }
+    }
+}
+
+impl fmt::Display for Register {
+    /// Prism Assembly Language name of the register (e.g. `$SC`, `$2`)
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Register::SC => proto::Registers::Sc,
+            Register::SF => proto::Registers::Sf,
+            Register::PC => proto::Registers::Pc,
+            Register::PP => proto::Registers::Pp,
+            Register::RV => proto::Registers::Rv,
+            Register::R0 => proto::Registers::R0,
+            Register::R1 => proto::Registers::R1,
+            Register::PO => proto::Registers::Po,
+            Register::GeneralPurpose(n) => return write!(f, "${}", n.number()),
+        };
+        write!(f, "${}", name.as_str_name())
+    }
+}
@@ -0,0 +1,322 @@
+use crate::error::Error;
+use crate::proto;
+use crate::registers::Register;
+
+/// Amount of bytes used by a [proto::ResponseType::Register] response
+pub const REGISTER_RESPONSE_LENGTH: usize = 3;
+
+/// Maximum amount of registers in a [proto::ResponseType::Dump] response
+pub const MAX_DUMP_REGISTERS: usize = u8::MAX as usize;
+
+/// Reply sent by a device when GET is executed, in Prism Response Format\
+/// the first byte is the [proto::ResponseType] followed by the address and value of every register\
+/// [proto::RegisterDump] is the protobuf representation of any response, a [Response::Register]
+/// becomes a dump with a single register so converting it back always gives a [Response::Dump]
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Response {
+    /// Contents of a single register
+    Register(Register, u8),
+    /// Contents of multiple registers
+    Dump(Vec<(Register, u8)>),
+}
+
+/// Write a [proto::ResponseType::Register] response at the beginning of [buffer] without allocating\
+/// returns the amount of bytes written ([REGISTER_RESPONSE_LENGTH])
+pub fn encode_register_into(
+    register: Register,
+    value: u8,
+    buffer: &mut [u8],
+) -> Result<usize, Error> {
+    let available = buffer.len();
+    let destination = buffer
+        .get_mut(..REGISTER_RESPONSE_LENGTH)
+        .ok_or(Error::Truncated {
+            expected: REGISTER_RESPONSE_LENGTH,
+            available,
+        })?;
+
+    destination.copy_from_slice(&[proto::ResponseType::Register as u8, register.into(), value]);
+    Ok(REGISTER_RESPONSE_LENGTH)
+}
+
+/// Write a [proto::ResponseType::Dump] response at the beginning of [buffer] without allocating\
+/// returns the amount of bytes written, fails if there are more than [MAX_DUMP_REGISTERS] registers
+pub fn encode_dump_into(registers: &[(Register, u8)], buffer: &mut [u8]) -> Result<usize, Error> {
+    let count =
+        u8::try_from(registers.len()).map_err(|_| Error::TooManyRegisters(registers.len()))?;
+
+    let length = 2 + 2 * registers.len();
+    let available = buffer.len();
+    let destination = buffer.get_mut(..length).ok_or(Error::Truncated {
+        expected: length,
+        available,
+    })?;
+
+    destination[0] = proto::ResponseType::Dump as u8;
+    destination[1] = count;
+    for (pair, &(register, value)) in destination[2..].chunks_exact_mut(2).zip(registers) {
+        pair.copy_from_slice(&[register.into(), value]);
+    }
+
+    Ok(length)
+}
+
+/// Amount of bytes used by the response at the beginning of [bytes], it can be known from the
+/// first 2 bytes, fails if the response type is unknown
+pub fn response_length(bytes: &[u8]) -> Result<usize, Error> {
+    let truncated = |expected| Error::Truncated {
+        expected,
+        available: bytes.len(),
+    };
+
+    let response_type = *bytes.first().ok_or(truncated(1))?;
+    match proto::ResponseType::try_from(response_type as i32) {
+        Ok(proto::ResponseType::Register) => Ok(REGISTER_RESPONSE_LENGTH),
+        Ok(proto::ResponseType::Dump) => {
+            let count = *bytes.get(1).ok_or(truncated(2))?;
+            Ok(2 + 2 * count as usize)
+        }
+        Err(_) => Err(Error::UnknownResponse(response_type)),
+    }
+}
+
+#[cfg(feature = "std")]
+impl Response {
+    /// Transform the response into Prism Response Format
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let mut buffer = match self {
+            Response::Register(..) => vec![0; REGISTER_RESPONSE_LENGTH],
+            Response::Dump(registers) => vec![0; 2 + 2 * registers.len()],
+        };
+
+        match self {
+            Response::Register(register, value) => {
+                encode_register_into(*register, *value, &mut buffer)?
+            }
+            Response::Dump(registers) => encode_dump_into(registers, &mut buffer)?,
+        };
+        Ok(buffer)
+    }
+
+    /// Decode the response at the beginning of [bytes] (Prism Response Format)\
+    /// returns the response and the amount of bytes it used
+    pub fn decode(bytes: &[u8]) -> Result<(Response, usize), Error> {
+        let length = response_length(bytes)?;
+        let bytes = bytes.get(..length).ok_or(Error::Truncated {
+            expected: length,
+            available: bytes.len(),
+        })?;
+
+        let pair = |pair: &[u8]| Ok((Register::try_from(pair[0])?, pair[1]));
+        let response = match proto::ResponseType::try_from(bytes[0] as i32) {
+            Ok(proto::ResponseType::Register) => {
+                let (register, value) = pair(&bytes[1..])?;
+                Response::Register(register, value)
+            }
+            _ => Response::Dump(
+                bytes[2..]
+                    .chunks_exact(2)
+                    .map(pair)
+                    .collect::<Result<_, Error>>()?,
+            ),
+        };
+
+        Ok((response, length))
+    }
+
+    /// Contents of [register] if they are part of the response
+    pub fn get(&self, register: Register) -> Option<u8> {
+        match self {
+            Response::Register(found, value) => (*found == register).then_some(*value),
+            Response::Dump(registers) => registers
+                .iter()
+                .find(|(found, _)| *found == register)
+                .map(|&(_, value)| value),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Response> for proto::RegisterDump {
+    /// Every register in the response (a single one for [Response::Register])
+    fn from(response: Response) -> Self {
+        let registers = match response {
+            Response::Register(register, value) => vec![(register, value)],
+            Response::Dump(registers) => registers,
+        };
+
+        proto::RegisterDump {
+            registers: registers
+                .into_iter()
+                .map(|(register, value)| proto::RegisterValue {
+                    address: u8::from(register) as u32,
+                    value: value as u32,
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl TryFrom<proto::RegisterDump> for Response {
+    type Error = Error;
+
+    /// Always a [Response::Dump], fails if an address or a value does not fit in a byte or if an
+    /// address does not belong to any register
+    fn try_from(dump: proto::RegisterDump) -> Result<Self, Self::Error> {
+        let byte = |number: u32| u8::try_from(number).map_err(|_| Error::ByteOutOfRange(number));
+
+        dump.registers
+            .into_iter()
+            .map(|register| {
+                Ok((
+                    Register::try_from(byte(register.address)?)?,
+                    byte(register.value)?,
+                ))
+            })
+            .collect::<Result<_, Error>>()
+            .map(Response::Dump)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_response() {
+        let mut buffer = [0xff; 4];
+        assert_eq!(encode_register_into(Register::SF, 0x2a, &mut buffer), Ok(3));
+        assert_eq!(buffer, [0x00, 0x01, 0x2a, 0xff]);
+
+        assert_eq!(
+            encode_register_into(Register::SF, 0x2a, &mut buffer[..2]),
+            Err(Error::Truncated {
+                expected: 3,
+                available: 2
+            })
+        );
+    }
+
+    #[test]
+    fn dump_response() {
+        let registers = [(Register::PC, 7), (Register::general(0).unwrap(), 200)];
+        let mut buffer = [0xff; 7];
+        assert_eq!(encode_dump_into(&registers, &mut buffer), Ok(6));
+        assert_eq!(buffer, [0x01, 0x02, 0x03, 7, 0x0a, 200, 0xff]);
+
+        assert_eq!(encode_dump_into(&[], &mut buffer), Ok(2));
+        assert_eq!(buffer[..2], [0x01, 0x00]);
+
+        assert_eq!(
+            encode_dump_into(&registers, &mut buffer[..5]),
+            Err(Error::Truncated {
+                expected: 6,
+                available: 5
+            })
+        );
+        assert_eq!(
+            encode_dump_into(&[(Register::SC, 0); 256], &mut buffer),
+            Err(Error::TooManyRegisters(256))
+        );
+    }
+
+    #[test]
+    fn length_from_the_first_bytes() {
+        assert_eq!(response_length(&[0x00]), Ok(3));
+        assert_eq!(response_length(&[0x01, 0x03]), Ok(8));
+        assert_eq!(response_length(&[0x01, 0xff, 0x00]), Ok(512));
+        assert_eq!(
+            response_length(&[]),
+            Err(Error::Truncated {
+                expected: 1,
+                available: 0
+            })
+        );
+        assert_eq!(
+            response_length(&[0x01]),
+            Err(Error::Truncated {
+                expected: 2,
+                available: 1
+            })
+        );
+        assert_eq!(
+            response_length(&[0x02, 0x00]),
+            Err(Error::UnknownResponse(2))
+        );
+    }
+
+    #[test]
+    fn decode_inverts_encode() {
+        let responses = [
+            Response::Register(Register::RV, 1),
+            Response::Dump(vec![]),
+            Response::Dump(vec![(Register::SC, 3), (Register::PO, 4)]),
+        ];
+        for response in responses {
+            let mut bytes = response.encode().unwrap();
+            let length = bytes.len();
+            bytes.push(0xff);
+            assert_eq!(Response::decode(&bytes), Ok((response, length)));
+        }
+    }
+
+    #[test]
+    fn decode_rejects_truncated_and_unknown_responses() {
+        assert_eq!(
+            Response::decode(&[0x00, 0x01]),
+            Err(Error::Truncated {
+                expected: 3,
+                available: 2
+            })
+        );
+        assert_eq!(
+            Response::decode(&[0x01, 0x02, 0x00, 0x01, 0x03]),
+            Err(Error::Truncated {
+                expected: 6,
+                available: 5
+            })
+        );
+        assert_eq!(
+            Response::decode(&[0x07, 0x00, 0x01]),
+            Err(Error::UnknownResponse(7))
+        );
+        assert_eq!(
+            Response::decode(&[0x00, 0x02, 0x01]),
+            Err(Error::InvalidRegister(2))
+        );
+    }
+
+    #[test]
+    fn protobuf_dump() {
+        let dump = proto::RegisterDump::from(Response::Register(Register::PP, 9));
+        assert_eq!(
+            dump.registers,
+            [proto::RegisterValue {
+                address: 4,
+                value: 9
+            }]
+        );
+        assert_eq!(
+            Response::try_from(dump),
+            Ok(Response::Dump(vec![(Register::PP, 9)]))
+        );
+
+        let value = |address, value| proto::RegisterDump {
+            registers: vec![proto::RegisterValue { address, value }],
+        };
+        assert_eq!(
+            Response::try_from(value(256, 0)),
+            Err(Error::ByteOutOfRange(256))
+        );
+        assert_eq!(
+            Response::try_from(value(0, 300)),
+            Err(Error::ByteOutOfRange(300))
+        );
+        assert_eq!(
+            Response::try_from(value(9, 0)),
+            Err(Error::InvalidRegister(9))
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use core::slice;
+
+use crate::error::Error;
+use crate::instruction::{instruction_length, InstructionSet, MAX_INSTRUCTION_LENGTH};
+use crate::proto;
+
+/// Push-based decoder for Prism Binary Format received a few bytes at a time\
+/// Bytes are stored in a fixed buffer (no heap) until the instruction is complete\
+/// Unknown opcodes are dropped one byte at a time and invalid operands drop the whole instruction,
+/// the following byte is always taken as the start of a new instruction
+#[derive(Debug, Clone)]
+pub struct StreamDecoder {
+    /// Bytes of the incomplete instruction
+    buffer: [u8; MAX_INSTRUCTION_LENGTH],
+    /// Amount of bytes stored in [buffer]
+    length: usize,
+    /// Amount of bytes required by the incomplete instruction
+    expected: usize,
+    /// Amount of bytes pushed since the decoder was created
+    position: usize,
+}
+
+impl Default for StreamDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamDecoder {
+    /// Create a decoder waiting for the first byte of an instruction
+    pub const fn new() -> Self {
+        Self {
+            buffer: [0; MAX_INSTRUCTION_LENGTH],
+            length: 0,
+            expected: 0,
+            position: 0,
+        }
+    }
+
+    /// Receive a single byte\
+    /// returns the instruction once all of its operands arrived, or the error if it cannot be decoded
+    pub fn push(&mut self, byte: u8) -> Option<Result<InstructionSet, Error>> {
+        self.position += 1;
+
+        // First byte of an instruction, get its length from the opcode
+        if self.length == 0 {
+            let opcode = byte >> 2;
+            match proto::InstructionSet::try_from(opcode as i32) {
+                Ok(instruction) => self.expected = instruction_length(instruction),
+                // Drop only this byte, the next one may be a valid instruction
+                Err(_) => return Some(Err(Error::UnknownOpcode(opcode))),
+            }
+        }
+
+        self.buffer[self.length] = byte;
+        self.length += 1;
+
+        if self.length < self.expected {
+            return None;
+        }
+
+        // Instruction is complete, the next byte starts a new one
+        let length = core::mem::take(&mut self.length);
+        Some(InstructionSet::decode(&self.buffer[..length]).map(|(instruction, _)| instruction))
+    }
+
+    /// Receive a chunk of bytes, the returned iterator yields every instruction (or error) as it
+    /// is completed\
+    /// Beware: bytes are only consumed as the iterator advances
+    pub fn feed<'a>(&'a mut self, bytes: &'a [u8]) -> Decoded<'a> {
+        Decoded {
+            decoder: self,
+            bytes: bytes.iter(),
+        }
+    }
+
+    /// Bytes received of the instruction that is not yet complete
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer[..self.length]
+    }
+
+    /// Whether the decoder is waiting for the first byte of an instruction
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    /// Amount of bytes received since the decoder was created
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Drop the incomplete instruction
+    pub fn clear(&mut self) {
+        self.length = 0;
+    }
+}
+
+/// Iterator over the instructions completed by a chunk of bytes, see [StreamDecoder::feed]
+pub struct Decoded<'a> {
+    decoder: &'a mut StreamDecoder,
+    bytes: slice::Iter<'a, u8>,
+}
+
+impl Iterator for Decoded<'_> {
+    type Item = Result<InstructionSet, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for &byte in self.bytes.by_ref() {
+            if let Some(result) = self.decoder.push(byte) {
+                return Some(result);
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::addressing::AddressingMode;
+    use crate::arrays::{Array2, Array3};
+    use crate::registers::Register;
+
+    fn decode(decoder: &mut StreamDecoder, bytes: &[u8]) -> Vec<Result<InstructionSet, Error>> {
+        decoder.feed(bytes).collect()
+    }
+
+    #[test]
+    fn unknown_opcodes_are_dropped_one_byte_at_a_time() {
+        let mut decoder = StreamDecoder::new();
+        assert_eq!(
+            decode(&mut decoder, &[0x38, 0xFF, 0x04, 0x28, 42]),
+            [
+                Err(Error::UnknownOpcode(14)),
+                Err(Error::UnknownOpcode(63)),
+                Ok(InstructionSet::BEGIN),
+                Ok(InstructionSet::JMP(42)),
+            ]
+        );
+        assert!(decoder.is_empty());
+        assert_eq!(decoder.position(), 5);
+    }
+
+    #[test]
+    fn invalid_operands_drop_the_whole_instruction() {
+        let mut decoder = StreamDecoder::new();
+
+        // Operands are never taken as the start of an instruction (0x08 is RUN)
+        assert_eq!(
+            decode(
+                &mut decoder,
+                &[0x78, 2, 0x08, 0x70, 4, 0x08, 0x42, 0x08, 5, 0x08]
+            ),
+            [
+                Err(Error::InvalidRegister(2)),
+                Ok(InstructionSet::RUN),
+                Err(Error::InvalidDelayCode(4)),
+                Err(Error::InvalidAddressing(0x42)),
+                Ok(InstructionSet::RUN),
+            ]
+        );
+        assert!(decoder.is_empty());
+    }
+
+    #[test]
+    fn instructions_are_completed_across_chunks() {
+        let instructions = [
+            InstructionSet::BEGIN,
+            InstructionSet::FILL(
+                Array2(AddressingMode::Immediate(0), AddressingMode::Immediate(100)),
+                Array3(
+                    AddressingMode::Immediate(120),
+                    AddressingMode::Immediate(255),
+                    AddressingMode::Immediate(128),
+                ),
+            ),
+            InstructionSet::GET(Register::SF),
+            InstructionSet::BNE(
+                AddressingMode::Indirect(Register::general(0).unwrap()),
+                AddressingMode::Immediate(5),
+                0,
+            ),
+            InstructionSet::RUN,
+        ];
+
+        let mut bytes = vec![];
+        for instruction in instructions {
+            bytes.extend(instruction.encode().unwrap());
+        }
+
+        // Split the stream in three chunks at every possible pair of boundaries
+        for first in 0..=bytes.len() {
+            for second in first..=bytes.len() {
+                let mut decoder = StreamDecoder::new();
+                let mut decoded = vec![];
+                for chunk in [&bytes[..first], &bytes[first..second], &bytes[second..]] {
+                    decoded.extend(decoder.feed(chunk).map(Result::unwrap));
+                }
+
+                assert_eq!(decoded, instructions, "split at {} and {}", first, second);
+                assert!(decoder.is_empty());
+            }
+        }
+    }
+
+    #[test]
+    fn incomplete_instructions_are_pending() {
+        let mut decoder = StreamDecoder::new();
+        assert_eq!(decode(&mut decoder, &[0x4C, 0, 100]), []);
+        assert_eq!(decoder.pending(), [0x4C, 0, 100]);
+        assert!(!decoder.is_empty());
+
+        decoder.clear();
+        assert!(decoder.is_empty());
+        assert_eq!(decode(&mut decoder, &[0x08]), [Ok(InstructionSet::RUN)]);
+        assert_eq!(decoder.position(), 4);
+    }
+
+    #[test]
+    fn bytes_are_consumed_as_the_iterator_advances() {
+        let mut decoder = StreamDecoder::new();
+        assert_eq!(
+            decoder.feed(&[0x04, 0x28, 42]).next(),
+            Some(Ok(InstructionSet::BEGIN))
+        );
+        assert_eq!(decoder.position(), 1);
+        assert!(decoder.is_empty());
+    }
+}
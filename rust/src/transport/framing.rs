@@ -0,0 +1,572 @@
+#[cfg(feature = "std")]
+use std::io::{Read, Write};
+
+use crate::error::Error;
+
+/// Marks the beginning of a frame
+pub const START: u8 = 0x7E;
+/// Precedes a stuffed byte, the following byte must be xor'ed with [STUFFING]
+pub const ESCAPE: u8 = 0x7D;
+/// Applied (xor) to [START] and [ESCAPE] when they appear inside a frame
+pub const STUFFING: u8 = 0x20;
+
+/// Sequence number (1 byte) and payload length (2 bytes)
+const HEADER_LENGTH: usize = 3;
+/// CRC of the header and the payload
+const CRC_LENGTH: usize = 2;
+
+/// Maximum amount of bytes used by a frame with the given payload length (every byte stuffed)
+pub const fn max_frame_length(payload: usize) -> usize {
+    1 + 2 * (HEADER_LENGTH + payload + CRC_LENGTH)
+}
+
+/// Continue a CRC-16/CCITT-FALSE computation (polynomial 0x1021) with [bytes]
+const fn crc16_update(mut crc: u16, bytes: &[u8]) -> u16 {
+    let mut index = 0;
+    while index < bytes.len() {
+        crc ^= (bytes[index] as u16) << 8;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 0x8000 != 0 {
+                (crc << 1) ^ 0x1021
+            } else {
+                crc << 1
+            };
+            bit += 1;
+        }
+        index += 1;
+    }
+    crc
+}
+
+/// CRC-16/CCITT-FALSE of [bytes], used to detect corrupted frames
+pub const fn crc16(bytes: &[u8]) -> u16 {
+    crc16_update(0xFFFF, bytes)
+}
+
+/// Writes stuffed bytes into a buffer
+struct Stuffer<'a> {
+    buffer: &'a mut [u8],
+    length: usize,
+}
+
+impl Stuffer<'_> {
+    fn push(&mut self, byte: u8) -> Result<(), Error> {
+        match byte {
+            START | ESCAPE => self.write(&[ESCAPE, byte ^ STUFFING]),
+            _ => self.write(&[byte]),
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let available = self.buffer.len();
+        let destination = self
+            .buffer
+            .get_mut(self.length..self.length + bytes.len())
+            .ok_or(Error::Truncated {
+                expected: self.length + bytes.len(),
+                available,
+            })?;
+
+        destination.copy_from_slice(bytes);
+        self.length += bytes.len();
+        Ok(())
+    }
+}
+
+/// Wrap [payload] in a frame written at the beginning of [buffer] without allocating\
+/// frames are made of [START] followed by the sequence number, the payload length (2 bytes), the payload
+/// and the CRC of everything but [START] (2 bytes, see [crc16]), both numbers are big endian and
+/// every byte after [START] is stuffed so that [START] never appears inside a frame\
+/// returns the amount of bytes written, fails if the payload does not fit in the length field
+/// or if the buffer is too small (see [max_frame_length])
+pub fn frame_into(sequence: u8, payload: &[u8], buffer: &mut [u8]) -> Result<usize, Error> {
+    let length = u16::try_from(payload.len()).map_err(|_| Error::FrameTooLong {
+        length: payload.len(),
+        capacity: u16::MAX as usize,
+    })?;
+
+    let [high, low] = length.to_be_bytes();
+    let header = [sequence, high, low];
+    let crc = crc16_update(crc16(&header), payload);
+
+    let mut stuffer = Stuffer { buffer, length: 0 };
+    stuffer.write(&[START])?;
+    for &byte in header.iter().chain(payload).chain(&crc.to_be_bytes()) {
+        stuffer.push(byte)?;
+    }
+
+    Ok(stuffer.length)
+}
+
+/// Wraps payloads in frames numbering them in sequence, see [frame_into]
+///
+/// ```
+/// use libnewton::transport::framing::{Deframer, Framer};
+///
+/// let mut framer = Framer::new();
+/// let mut pipe = framer.frame(&[0x04, 0x08]).unwrap();
+/// pipe.extend(framer.frame(&[0x7e, 0x7d]).unwrap());
+///
+/// let mut deframer = Deframer::<64>::new();
+/// let mut payloads = vec![];
+/// for byte in pipe {
+///     if let Some(frame) = deframer.push(byte) {
+///         let frame = frame.unwrap();
+///         payloads.push((frame.sequence, frame.payload.to_vec()));
+///     }
+/// }
+///
+/// assert_eq!(payloads, [(0, vec![0x04, 0x08]), (1, vec![0x7e, 0x7d])]);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Framer {
+    sequence: u8,
+}
+
+impl Framer {
+    /// Create a framer whose first frame has sequence number 0
+    pub const fn new() -> Self {
+        Self { sequence: 0 }
+    }
+
+    /// Sequence number of the next frame
+    pub fn sequence(&self) -> u8 {
+        self.sequence
+    }
+
+    /// Wrap [payload] in the next frame, see [frame_into]
+    pub fn frame_into(&mut self, payload: &[u8], buffer: &mut [u8]) -> Result<usize, Error> {
+        let length = frame_into(self.sequence, payload, buffer)?;
+        self.sequence = self.sequence.wrapping_add(1);
+        Ok(length)
+    }
+
+    /// Wrap [payload] in the next frame
+    #[cfg(feature = "std")]
+    pub fn frame(&mut self, payload: &[u8]) -> Result<Vec<u8>, Error> {
+        let mut buffer = vec![0; max_frame_length(payload.len())];
+        let length = self.frame_into(payload, &mut buffer)?;
+        buffer.truncate(length);
+        Ok(buffer)
+    }
+}
+
+/// Frame received by a [Deframer]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub sequence: u8,
+    pub payload: &'a [u8],
+}
+
+/// Push-based receiver of frames, payloads are stored in a fixed buffer of [N] bytes (no heap)\
+/// Bytes outside of a frame are ignored, a start marker always begins a new frame (dropping the
+/// incomplete one) so the receiver resynchronizes after losing or corrupting bytes
+#[derive(Debug, Clone)]
+pub struct Deframer<const N: usize> {
+    header: [u8; HEADER_LENGTH],
+    payload: [u8; N],
+    crc: [u8; CRC_LENGTH],
+    /// Payload length read from the header
+    length: usize,
+    /// Unstuffed bytes received of the current frame (after the start marker)
+    received: usize,
+    /// Previous byte was [ESCAPE]
+    escaped: bool,
+    /// A start marker was received and the frame is not complete
+    synchronized: bool,
+}
+
+impl<const N: usize> Default for Deframer<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> Deframer<N> {
+    /// Create a deframer waiting for a start marker
+    pub const fn new() -> Self {
+        Self {
+            header: [0; HEADER_LENGTH],
+            payload: [0; N],
+            crc: [0; CRC_LENGTH],
+            length: 0,
+            received: 0,
+            escaped: false,
+            synchronized: false,
+        }
+    }
+
+    /// Wait for the next start marker
+    fn desynchronize(&mut self) {
+        self.synchronized = false;
+        self.escaped = false;
+        self.received = 0;
+        self.length = 0;
+    }
+
+    /// Receive a single byte\
+    /// returns the frame once it is complete, or the error if it was interrupted by another
+    /// start marker, does not fit in the buffer or is corrupted
+    pub fn push(&mut self, byte: u8) -> Option<Result<Frame<'_>, Error>> {
+        if byte == START {
+            let interrupted = self.synchronized && self.received > 0;
+            let error = Error::Truncated {
+                expected: HEADER_LENGTH + self.length + CRC_LENGTH,
+                available: self.received,
+            };
+
+            self.desynchronize();
+            self.synchronized = true;
+            return interrupted.then_some(Err(error));
+        }
+
+        if !self.synchronized {
+            return None;
+        }
+
+        if byte == ESCAPE {
+            self.escaped = true;
+            return None;
+        }
+
+        let byte = if core::mem::take(&mut self.escaped) {
+            byte ^ STUFFING
+        } else {
+            byte
+        };
+
+        let position = self.received;
+        self.received += 1;
+
+        if position < HEADER_LENGTH {
+            self.header[position] = byte;
+            if self.received == HEADER_LENGTH {
+                self.length = u16::from_be_bytes([self.header[1], self.header[2]]) as usize;
+                if self.length > N {
+                    let error = Error::FrameTooLong {
+                        length: self.length,
+                        capacity: N,
+                    };
+                    self.desynchronize();
+                    return Some(Err(error));
+                }
+            }
+            return None;
+        }
+
+        if position < HEADER_LENGTH + self.length {
+            self.payload[position - HEADER_LENGTH] = byte;
+            return None;
+        }
+
+        self.crc[position - HEADER_LENGTH - self.length] = byte;
+        if self.received < HEADER_LENGTH + self.length + CRC_LENGTH {
+            return None;
+        }
+
+        // Frame is complete, bytes are ignored until the next start marker
+        let length = self.length;
+        self.desynchronize();
+
+        let payload = &self.payload[..length];
+        let expected = crc16_update(crc16(&self.header), payload);
+        let found = u16::from_be_bytes(self.crc);
+        if expected != found {
+            return Some(Err(Error::Checksum { expected, found }));
+        }
+
+        Some(Ok(Frame {
+            sequence: self.header[0],
+            payload,
+        }))
+    }
+
+    /// Whether a frame is being received
+    pub fn is_receiving(&self) -> bool {
+        self.synchronized && self.received > 0
+    }
+}
+
+/// Writes payloads as frames into a byte stream (e.g. a serial port or a pipe)
+#[cfg(feature = "std")]
+pub struct FrameWriter<W: Write> {
+    writer: W,
+    framer: Framer,
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> FrameWriter<W> {
+    /// Write frames into [writer]
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer,
+            framer: Framer::new(),
+        }
+    }
+
+    /// Write [payload] as the next frame and flush it, returns its sequence number
+    pub fn send(&mut self, payload: &[u8]) -> Result<u8, Error> {
+        let sequence = self.framer.sequence();
+        let frame = self.framer.frame(payload)?;
+        self.writer.write_all(&frame)?;
+        self.writer.flush()?;
+        Ok(sequence)
+    }
+
+    /// Get the underlying writer
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.writer
+    }
+
+    /// Take the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads frames of at most [N] bytes from a byte stream (e.g. a serial port or a pipe)
+#[cfg(feature = "std")]
+pub struct FrameReader<R: Read, const N: usize> {
+    reader: R,
+    deframer: Deframer<N>,
+    /// Bytes read and not yet pushed into the deframer
+    buffer: [u8; 64],
+    start: usize,
+    end: usize,
+}
+
+#[cfg(feature = "std")]
+impl<R: Read, const N: usize> FrameReader<R, N> {
+    /// Read frames from [reader]
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            deframer: Deframer::new(),
+            buffer: [0; 64],
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Block until the next frame is received, returns its sequence number and payload\
+    /// corrupted frames are reported as errors, calling again resumes on the following frame\
+    /// fails with [std::io::ErrorKind::UnexpectedEof] if the stream ends
+    pub fn receive(&mut self) -> Result<(u8, Vec<u8>), Error> {
+        loop {
+            if self.start == self.end {
+                self.start = 0;
+                self.end = self.reader.read(&mut self.buffer)?;
+                if self.end == 0 {
+                    return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into());
+                }
+            }
+
+            let byte = self.buffer[self.start];
+            self.start += 1;
+            if let Some(frame) = self.deframer.push(byte) {
+                return frame.map(|frame| (frame.sequence, frame.payload.to_vec()));
+            }
+        }
+    }
+
+    /// Get the underlying reader
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.reader
+    }
+
+    /// Take the underlying reader
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Push every byte into [deframer], collecting the frames and errors it reports
+    fn deframe<const N: usize>(
+        deframer: &mut Deframer<N>,
+        bytes: &[u8],
+    ) -> Vec<Result<(u8, Vec<u8>), Error>> {
+        let mut frames = vec![];
+        for &byte in bytes {
+            if let Some(frame) = deframer.push(byte) {
+                frames.push(frame.map(|frame| (frame.sequence, frame.payload.to_vec())));
+            }
+        }
+        frames
+    }
+
+    fn frame(sequence: u8, payload: &[u8]) -> Vec<u8> {
+        let mut buffer = vec![0; max_frame_length(payload.len())];
+        let length = frame_into(sequence, payload, &mut buffer).unwrap();
+        buffer.truncate(length);
+        buffer
+    }
+
+    #[test]
+    fn crc16_check_value() {
+        assert_eq!(crc16(b"123456789"), 0x29B1);
+        assert_eq!(crc16(&[]), 0xFFFF);
+        assert_eq!(crc16_update(crc16(b"1234"), b"56789"), 0x29B1);
+    }
+
+    #[test]
+    fn frame_layout() {
+        assert_eq!(
+            frame(5, &[0x01, 0x02]),
+            [START, 5, 0x00, 0x02, 0x01, 0x02, 0x4f, 0x48]
+        );
+        assert_eq!(frame(0, &[]), [START, 0, 0x00, 0x00, 0xcc, 0x9c]);
+    }
+
+    #[test]
+    fn markers_are_stuffed_in_payload_and_crc() {
+        // CRC is 0x7e55
+        let bytes = frame(0x00, &[0x7e, 0x7d, 0x36]);
+        assert_eq!(
+            bytes,
+            [START, 0x00, 0x00, 0x03, ESCAPE, 0x5e, ESCAPE, 0x5d, 0x36, ESCAPE, 0x5e, 0x55]
+        );
+        assert!(!bytes[1..].contains(&START));
+
+        let mut deframer = Deframer::<8>::new();
+        assert_eq!(
+            deframe(&mut deframer, &bytes),
+            [Ok((0x00, vec![0x7e, 0x7d, 0x36]))]
+        );
+
+        // Sequence numbers are stuffed too
+        let bytes = frame(0x7d, &[]);
+        assert_eq!(bytes[1..3], [ESCAPE, 0x5d]);
+        assert_eq!(deframe(&mut deframer, &bytes), [Ok((0x7d, vec![]))]);
+    }
+
+    #[test]
+    fn flipped_byte_fails_the_checksum() {
+        let mut bytes = frame(1, &[0x01, 0x02]);
+        bytes[5] ^= 0x40;
+
+        let mut deframer = Deframer::<8>::new();
+        assert_eq!(
+            deframe(&mut deframer, &bytes),
+            [Err(Error::Checksum {
+                expected: crc16(&[1, 0x00, 0x02, 0x01, 0x42]),
+                found: 0xc64e,
+            })]
+        );
+        assert!(!deframer.is_receiving());
+    }
+
+    #[test]
+    fn resynchronizes_after_a_dropped_byte() {
+        let mut bytes = frame(0, &[0x01, 0x02, 0x03]);
+        bytes.remove(5);
+        bytes.extend(frame(1, &[0x04]));
+
+        let mut deframer = Deframer::<8>::new();
+        assert_eq!(
+            deframe(&mut deframer, &bytes),
+            [
+                Err(Error::Truncated {
+                    expected: 8,
+                    available: 7
+                }),
+                Ok((1, vec![0x04]))
+            ]
+        );
+    }
+
+    #[test]
+    fn resynchronizes_after_a_stray_start() {
+        // Noise before the first marker is ignored, a marker right before another is harmless
+        let mut bytes = vec![0x01, 0x02, START, START, 0x00];
+        bytes.extend(frame(2, &[0x05]));
+
+        let mut deframer = Deframer::<8>::new();
+        assert_eq!(
+            deframe(&mut deframer, &bytes),
+            [
+                Err(Error::Truncated {
+                    expected: HEADER_LENGTH + CRC_LENGTH,
+                    available: 1
+                }),
+                Ok((2, vec![0x05]))
+            ]
+        );
+    }
+
+    #[test]
+    fn frame_too_long() {
+        let mut deframer = Deframer::<4>::new();
+        let mut bytes = frame(0, &[0; 5]);
+        bytes.extend(frame(1, &[0; 4]));
+        assert_eq!(
+            deframe(&mut deframer, &bytes),
+            [
+                Err(Error::FrameTooLong {
+                    length: 5,
+                    capacity: 4
+                }),
+                Ok((1, vec![0; 4]))
+            ]
+        );
+
+        let payload = vec![0; u16::MAX as usize + 1];
+        assert_eq!(
+            frame_into(0, &payload, &mut []),
+            Err(Error::FrameTooLong {
+                length: payload.len(),
+                capacity: u16::MAX as usize
+            })
+        );
+    }
+
+    #[test]
+    fn buffer_too_small() {
+        let mut buffer = [0; 7];
+        assert_eq!(
+            frame_into(0, &[0x01, 0x02], &mut buffer),
+            Err(Error::Truncated {
+                expected: 8,
+                available: 7
+            })
+        );
+
+        // Stuffed bytes take two
+        let mut buffer = [0; 5];
+        assert_eq!(
+            frame_into(0, &[START], &mut buffer),
+            Err(Error::Truncated {
+                expected: 6,
+                available: 5
+            })
+        );
+    }
+
+    #[test]
+    fn frames_survive_a_pipe() {
+        use std::collections::VecDeque;
+
+        let long: Vec<u8> = (0..=255).collect();
+        let mut writer = FrameWriter::new(VecDeque::new());
+        assert_eq!(writer.send(&[0x04, 0x08]).unwrap(), 0);
+        assert_eq!(writer.send(&long).unwrap(), 1);
+        assert_eq!(writer.send(&[START, ESCAPE]).unwrap(), 2);
+
+        // Corrupt the first payload byte of the second frame
+        let mut pipe = writer.into_inner();
+        pipe[12] ^= 0x01;
+
+        let mut reader = FrameReader::<_, 256>::new(pipe);
+        assert_eq!(reader.receive().unwrap(), (0, vec![0x04, 0x08]));
+        assert!(matches!(reader.receive(), Err(Error::Checksum { .. })));
+        assert_eq!(reader.receive().unwrap(), (2, vec![START, ESCAPE]));
+        assert!(matches!(
+            reader.receive(),
+            Err(Error::Io(std::io::ErrorKind::UnexpectedEof, _))
+        ));
+    }
+}
@@ -0,0 +1,84 @@
+use std::collections::VecDeque;
+use std::io;
+use std::time::Duration;
+
+use crate::arrays::Array3;
+use crate::error::Error;
+use crate::machine::{Machine, Step};
+use crate::response::{encode_register_into, REGISTER_RESPONSE_LENGTH};
+use crate::transport::Transport;
+
+/// Maximum amount of instructions executed after every send, stops scripts that never end
+const STEPS: usize = 10_000;
+
+/// In-process device backed by the reference interpreter ([Machine]), useful for tests\
+/// Instructions are executed as soon as they are sent and DELAY does not wait,
+/// GET responses are sent back in Prism Response Format
+pub struct LoopbackTransport {
+    machine: Machine<Vec<Array3<u8>>>,
+    /// Bytes sent back by the machine and not yet received
+    output: VecDeque<u8>,
+}
+
+impl LoopbackTransport {
+    /// Create a device with the given amount of LEDs
+    pub fn new(leds: usize) -> Self {
+        Self {
+            machine: Machine::new(vec![Array3(0, 0, 0); leds]),
+            output: VecDeque::new(),
+        }
+    }
+
+    /// Get the machine executing the instructions
+    pub fn machine(&self) -> &Machine<Vec<Array3<u8>>> {
+        &self.machine
+    }
+
+    /// Get the machine executing the instructions as mutable
+    pub fn machine_mut(&mut self) -> &mut Machine<Vec<Array3<u8>>> {
+        &mut self.machine
+    }
+}
+
+impl Transport for LoopbackTransport {
+    /// Feed the bytes to the machine and execute them (and the running script) until it is idle\
+    /// fails with the first error found while executing
+    fn send(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.machine.feed(bytes);
+
+        let mut result = Ok(());
+        for _ in 0..STEPS {
+            match self.machine.step() {
+                Ok(Step::Idle) => break,
+                Ok(_) => {}
+                Err(error) if result.is_ok() => result = Err(error),
+                Err(_) => {}
+            }
+        }
+
+        for (register, value) in self.machine.take_responses() {
+            let mut response = [0; REGISTER_RESPONSE_LENGTH];
+            encode_register_into(register, value, &mut response)?;
+            self.output.extend(response);
+        }
+
+        result
+    }
+
+    /// Never waits, fails with [io::ErrorKind::TimedOut] if there is nothing to receive
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        if self.output.is_empty() && !buffer.is_empty() {
+            return Err(io::Error::from(io::ErrorKind::TimedOut).into());
+        }
+
+        let length = buffer.len().min(self.output.len());
+        for (destination, byte) in buffer.iter_mut().zip(self.output.drain(..length)) {
+            *destination = byte;
+        }
+        Ok(length)
+    }
+
+    fn set_timeout(&mut self, _: Option<Duration>) -> Result<(), Error> {
+        Ok(())
+    }
+}
@@ -0,0 +1,285 @@
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::time::Duration;
+
+#[cfg(feature = "std")]
+use crate::binary;
+#[cfg(feature = "std")]
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::instruction::InstructionSet;
+#[cfg(feature = "std")]
+use crate::proto;
+#[cfg(feature = "std")]
+use crate::registers::Register;
+#[cfg(feature = "std")]
+use crate::response::{self, Response};
+
+pub mod framing;
+#[cfg(feature = "std")]
+pub mod loopback;
+#[cfg(feature = "serial")]
+pub mod serial;
+#[cfg(feature = "std")]
+pub mod tcp;
+#[cfg(feature = "std")]
+pub mod udp;
+
+/// Connection to a device that receives Prism Binary Format\
+/// Implementors only move bytes around, instructions are assembled by the provided methods
+#[cfg(feature = "std")]
+pub trait Transport {
+    /// Send raw bytes to the device
+    fn send(&mut self, bytes: &[u8]) -> Result<(), Error>;
+
+    /// Receive bytes sent back by the device (e.g. GET responses) into [buffer]\
+    /// returns the amount of bytes received, blocks until something is received or the timeout
+    /// expires (fails with [io::ErrorKind::TimedOut])
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error>;
+
+    /// Maximum time [Transport::receive] waits for the device, [None] waits forever
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Error>;
+
+    /// Assemble and send the instructions as they are
+    fn send_instructions(&mut self, instructions: &[InstructionSet]) -> Result<(), Error> {
+        self.send(&binary::assemble(instructions)?)
+    }
+
+    /// Send a script, it must start with BEGIN and end with RUN (the device runs it right away)
+    fn send_script(&mut self, script: &[InstructionSet]) -> Result<(), Error> {
+        if script.first() != Some(&InstructionSet::BEGIN) {
+            return Err(Error::ExpectedBegin);
+        }
+        if script.last() != Some(&InstructionSet::RUN) || script.len() < 2 {
+            return Err(Error::ExpectedRun);
+        }
+
+        self.send_instructions(script)
+    }
+
+    /// Switch the device to TRANSMIT mode and send the instructions to be executed right away
+    fn send_transmit(&mut self, instructions: &[InstructionSet]) -> Result<(), Error> {
+        let mut bytes = InstructionSet::TRANSMIT.encode()?;
+        bytes.extend(binary::assemble(instructions)?);
+        self.send(&bytes)
+    }
+
+    /// Receive exactly enough bytes to fill [buffer], see [Transport::receive]
+    fn receive_exact(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        let mut received = 0;
+        while received < buffer.len() {
+            match self.receive(&mut buffer[received..])? {
+                0 => return Err(io::Error::from(io::ErrorKind::UnexpectedEof).into()),
+                length => received += length,
+            }
+        }
+        Ok(())
+    }
+
+    /// Receive the next GET response sent by the device, see [Transport::receive]
+    fn receive_response(&mut self) -> Result<Response, Error> {
+        // Type and (for dumps) amount of registers
+        let mut bytes = vec![0; 1];
+        self.receive_exact(&mut bytes)?;
+        if bytes[0] == proto::ResponseType::Dump as u8 {
+            bytes.push(0);
+            self.receive_exact(&mut bytes[1..])?;
+        }
+
+        let length = response::response_length(&bytes)?;
+        let received = bytes.len();
+        bytes.resize(length, 0);
+        self.receive_exact(&mut bytes[received..])?;
+
+        Ok(Response::decode(&bytes)?.0)
+    }
+}
+
+/// Ask the device for the contents of [register] and wait for its response\
+/// responses for other registers (e.g. sent by a running script) are skipped, the device must not be
+/// receiving a script (between BEGIN and RUN) as GET would be stored instead of executed\
+/// fails with [io::ErrorKind::TimedOut] if the device does not respond, see [Transport::set_timeout]
+#[cfg(feature = "std")]
+pub fn read_register(transport: &mut impl Transport, register: Register) -> Result<u8, Error> {
+    transport.send_instructions(&[InstructionSet::GET(register)])?;
+
+    loop {
+        if let Some(value) = transport.receive_response()?.get(register) {
+            return Ok(value);
+        }
+    }
+}
+
+/// Sockets report an expired timeout as [io::ErrorKind::WouldBlock] on some platforms
+#[cfg(feature = "std")]
+fn timeout(error: io::Error) -> Error {
+    match error.kind() {
+        io::ErrorKind::WouldBlock => io::Error::from(io::ErrorKind::TimedOut).into(),
+        _ => error.into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::*;
+    use crate::arrays::Array3;
+    use crate::assembler;
+    use crate::flags::Mode;
+    use crate::transport::loopback::LoopbackTransport;
+
+    /// Device that answers with the given chunks (one per receive) and then closes the stream
+    struct Chunks(VecDeque<Vec<u8>>);
+
+    impl Transport for Chunks {
+        fn send(&mut self, _: &[u8]) -> Result<(), Error> {
+            Ok(())
+        }
+
+        fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+            let Some(mut chunk) = self.0.pop_front() else {
+                return Ok(0);
+            };
+
+            let length = buffer.len().min(chunk.len());
+            buffer[..length].copy_from_slice(&chunk[..length]);
+            if length < chunk.len() {
+                self.0.push_front(chunk.split_off(length));
+            }
+            Ok(length)
+        }
+
+        fn set_timeout(&mut self, _: Option<Duration>) -> Result<(), Error> {
+            Ok(())
+        }
+    }
+
+    fn chunks(chunks: &[&[u8]]) -> Chunks {
+        Chunks(chunks.iter().map(|chunk| chunk.to_vec()).collect())
+    }
+
+    fn parse(source: &str) -> Vec<InstructionSet> {
+        assembler::parse(source).unwrap()
+    }
+
+    #[test]
+    fn scripts_start_with_begin_and_end_with_run() {
+        let mut transport = LoopbackTransport::new(1);
+
+        for (script, error) in [
+            ("", Error::ExpectedBegin),
+            ("NOP; RUN", Error::ExpectedBegin),
+            ("RUN", Error::ExpectedBegin),
+            ("BEGIN", Error::ExpectedRun),
+            ("BEGIN; NOP", Error::ExpectedRun),
+        ] {
+            assert_eq!(
+                transport.send_script(&parse(script)),
+                Err(error),
+                "{}",
+                script
+            );
+        }
+        assert_eq!(transport.machine().status().mode(), Some(Mode::Transmit));
+
+        transport
+            .send_script(&parse("BEGIN; LPAINT 0 50; UPDATE; RUN"))
+            .unwrap();
+        assert_eq!(transport.machine().buffer(), &[Array3(0, 0, 50)]);
+        assert_eq!(transport.machine().status().mode(), Some(Mode::Script));
+    }
+
+    #[test]
+    fn send_transmit_leaves_script_mode() {
+        let mut transport = LoopbackTransport::new(1);
+
+        // Without TRANSMIT the instructions would be stored in the script being received
+        transport.send_instructions(&parse("BEGIN; NOP")).unwrap();
+        transport
+            .send_transmit(&parse("LPAINT 0 50; UPDATE"))
+            .unwrap();
+        assert_eq!(transport.machine().buffer(), &[Array3(0, 0, 50)]);
+        assert_eq!(transport.machine().status().mode(), Some(Mode::Transmit));
+    }
+
+    #[test]
+    fn receive_exact_fills_the_buffer() {
+        let mut transport = chunks(&[&[1, 2], &[3, 4]]);
+
+        let mut buffer = [0; 3];
+        transport.receive_exact(&mut buffer).unwrap();
+        assert_eq!(buffer, [1, 2, 3]);
+
+        let mut buffer = [0; 2];
+        assert!(matches!(
+            transport.receive_exact(&mut buffer),
+            Err(Error::Io(io::ErrorKind::UnexpectedEof, _))
+        ));
+    }
+
+    #[test]
+    fn receive_response_across_partial_reads() {
+        let mut transport = chunks(&[
+            &[0x01],
+            &[0x02, 0x03],
+            &[7, 0x0a],
+            &[200, 0x00, 0x01],
+            &[0x2a],
+            &[0x05, 0x00],
+        ]);
+
+        assert_eq!(
+            transport.receive_response(),
+            Ok(Response::Dump(vec![
+                (Register::PC, 7),
+                (Register::general(0).unwrap(), 200)
+            ]))
+        );
+        assert_eq!(
+            transport.receive_response(),
+            Ok(Response::Register(Register::SF, 0x2a))
+        );
+        assert_eq!(transport.receive_response(), Err(Error::UnknownResponse(5)));
+    }
+
+    #[test]
+    fn read_register_skips_other_registers() {
+        let mut transport = LoopbackTransport::new(1);
+        transport
+            .send_script(&parse("BEGIN; LOAD $0 5; GET $0; HALT 7; RUN"))
+            .unwrap();
+
+        // The response to the GET in the script is waiting
+        assert_eq!(read_register(&mut transport, Register::SC), Ok(7));
+        assert_eq!(
+            read_register(&mut transport, Register::general(0).unwrap()),
+            Ok(5)
+        );
+        assert!(matches!(
+            transport.receive(&mut [0]),
+            Err(Error::Io(io::ErrorKind::TimedOut, _))
+        ));
+    }
+
+    #[test]
+    fn read_register_times_out() {
+        // GET is stored in the script being received instead of executed
+        let mut transport = LoopbackTransport::new(1);
+        transport.send_instructions(&parse("BEGIN")).unwrap();
+        assert!(matches!(
+            read_register(&mut transport, Register::SC),
+            Err(Error::Io(io::ErrorKind::TimedOut, _))
+        ));
+    }
+
+    #[test]
+    fn truncated_response() {
+        let mut transport = chunks(&[&[0x01, 0x02, 0x03]]);
+        assert!(matches!(
+            transport.receive_response(),
+            Err(Error::Io(io::ErrorKind::UnexpectedEof, _))
+        ));
+    }
+}
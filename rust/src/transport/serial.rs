@@ -0,0 +1,59 @@
+use std::io::{self, Read, Write};
+use std::time::Duration;
+
+use serialport::SerialPort;
+
+use crate::error::Error;
+use crate::transport::{timeout, Transport};
+
+impl From<serialport::Error> for Error {
+    fn from(error: serialport::Error) -> Self {
+        let kind = match error.kind() {
+            serialport::ErrorKind::NoDevice => io::ErrorKind::NotFound,
+            serialport::ErrorKind::InvalidInput => io::ErrorKind::InvalidInput,
+            serialport::ErrorKind::Io(kind) => kind,
+            serialport::ErrorKind::Unknown => io::ErrorKind::Other,
+        };
+        Error::Io(kind, error.description)
+    }
+}
+
+/// Device connected to a serial port (e.g. `/dev/ttyUSB0` or `COM3`)
+pub struct SerialTransport {
+    port: Box<dyn SerialPort>,
+}
+
+impl SerialTransport {
+    /// Open the serial port at [path] (8N1 without flow control)
+    pub fn open(path: &str, baud_rate: u32) -> Result<Self, Error> {
+        let port = serialport::new(path, baud_rate)
+            .timeout(Duration::MAX)
+            .open()?;
+        Ok(Self { port })
+    }
+
+    /// Use an already open serial port
+    pub fn new(port: Box<dyn SerialPort>) -> Self {
+        Self { port }
+    }
+
+    /// Get the underlying serial port
+    pub fn port(&self) -> &dyn SerialPort {
+        self.port.as_ref()
+    }
+}
+
+impl Transport for SerialTransport {
+    fn send(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.port.write_all(bytes)?;
+        Ok(self.port.flush()?)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        self.port.read(buffer).map_err(timeout)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.port.set_timeout(timeout.unwrap_or(Duration::MAX))?)
+    }
+}
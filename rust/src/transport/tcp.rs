@@ -0,0 +1,87 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::transport::{timeout, Transport};
+
+/// Device reachable through a TCP stream
+pub struct TcpTransport {
+    stream: TcpStream,
+}
+
+impl TcpTransport {
+    /// Connect to the device listening at [address]
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, Error> {
+        let stream = TcpStream::connect(address)?;
+        // Instructions are small, send them right away
+        stream.set_nodelay(true)?;
+        Ok(Self { stream })
+    }
+
+    /// Use an already connected stream
+    pub fn new(stream: TcpStream) -> Self {
+        Self { stream }
+    }
+
+    /// Get the underlying stream
+    pub fn stream(&self) -> &TcpStream {
+        &self.stream
+    }
+}
+
+impl Transport for TcpTransport {
+    fn send(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.stream.write_all(bytes)?;
+        Ok(self.stream.flush()?)
+    }
+
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        self.stream.read(buffer).map_err(timeout)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.stream.set_read_timeout(timeout)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::net::TcpListener;
+
+    use super::*;
+
+    #[test]
+    fn exchange() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut transport = TcpTransport::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut device, _) = listener.accept().unwrap();
+
+        transport.send(&[0x04, 0x00, 0x08]).unwrap();
+        let mut received = [0; 3];
+        device.read_exact(&mut received).unwrap();
+        assert_eq!(received, [0x04, 0x00, 0x08]);
+
+        device.write_all(&[0x00, 0x01, 0x2a]).unwrap();
+        let mut buffer = [0; 3];
+        transport.receive_exact(&mut buffer).unwrap();
+        assert_eq!(buffer, [0x00, 0x01, 0x2a]);
+
+        // Nothing else is sent
+        transport
+            .set_timeout(Some(Duration::from_millis(10)))
+            .unwrap();
+        assert!(matches!(
+            transport.receive(&mut buffer),
+            Err(Error::Io(io::ErrorKind::TimedOut, _))
+        ));
+
+        // The device closes the connection
+        drop(device);
+        assert!(matches!(
+            transport.receive_exact(&mut buffer),
+            Err(Error::Io(io::ErrorKind::UnexpectedEof, _))
+        ));
+    }
+}
@@ -0,0 +1,135 @@
+use std::io;
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::transport::{timeout, Transport};
+
+/// Largest payload of a UDP datagram
+const MAX_DATAGRAM_LENGTH: usize = 65_507;
+
+/// Device reachable through UDP datagrams\
+/// Every send is a single datagram, datagrams may be lost or reordered so scripts should be short
+/// enough to fit in one (see [crate::transport::framing] for detecting corruption)
+pub struct UdpTransport {
+    socket: UdpSocket,
+    /// Last datagram, allocated once and reused by every receive
+    datagram: Box<[u8]>,
+    /// Bytes [start, end) of the last datagram not yet received
+    start: usize,
+    end: usize,
+}
+
+impl UdpTransport {
+    /// Bind to an ephemeral local port and send every datagram to the device at [address]\
+    /// the local address has the family (IPv4 or IPv6) of the device, if [address] resolves to
+    /// several addresses they are tried in order
+    pub fn connect(address: impl ToSocketAddrs) -> Result<Self, Error> {
+        let mut error = io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        );
+
+        for address in address.to_socket_addrs()? {
+            let local = match address {
+                SocketAddr::V4(_) => SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+                SocketAddr::V6(_) => SocketAddr::from((Ipv6Addr::UNSPECIFIED, 0)),
+            };
+            match UdpSocket::bind(local).and_then(|socket| {
+                socket.connect(address)?;
+                Ok(socket)
+            }) {
+                Ok(socket) => return Ok(Self::new(socket)),
+                Err(last) => error = last,
+            }
+        }
+
+        Err(error.into())
+    }
+
+    /// Use an already connected socket
+    pub fn new(socket: UdpSocket) -> Self {
+        Self {
+            socket,
+            datagram: vec![0; MAX_DATAGRAM_LENGTH].into_boxed_slice(),
+            start: 0,
+            end: 0,
+        }
+    }
+
+    /// Get the underlying socket
+    pub fn socket(&self) -> &UdpSocket {
+        &self.socket
+    }
+}
+
+impl Transport for UdpTransport {
+    fn send(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.socket.send(bytes)?;
+        Ok(())
+    }
+
+    /// Receive bytes of a single datagram, bytes that do not fit in [buffer] are kept for the next call
+    fn receive(&mut self, buffer: &mut [u8]) -> Result<usize, Error> {
+        // Empty datagrams are skipped, receiving 0 bytes would look like the end of the stream
+        while self.start == self.end && !buffer.is_empty() {
+            self.start = 0;
+            self.end = self.socket.recv(&mut self.datagram).map_err(timeout)?;
+        }
+
+        let length = buffer.len().min(self.end - self.start);
+        buffer[..length].copy_from_slice(&self.datagram[self.start..self.start + length]);
+        self.start += length;
+        Ok(length)
+    }
+
+    fn set_timeout(&mut self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.socket.set_read_timeout(timeout)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Connect a transport to a device socket bound to [address] and exchange datagrams
+    fn exchange(address: &str) {
+        let device = UdpSocket::bind(address).unwrap();
+        let mut transport = UdpTransport::connect(device.local_addr().unwrap()).unwrap();
+        assert_eq!(
+            transport.socket().local_addr().unwrap().is_ipv6(),
+            device.local_addr().unwrap().is_ipv6()
+        );
+
+        transport.send(&[0x04, 0x00, 0x08]).unwrap();
+        let mut datagram = [0; 8];
+        let (length, peer) = device.recv_from(&mut datagram).unwrap();
+        assert_eq!(datagram[..length], [0x04, 0x00, 0x08]);
+
+        // Empty datagrams are skipped, the rest of a datagram is kept for the next call
+        device.send_to(&[], peer).unwrap();
+        device.send_to(&[1, 2, 3, 4, 5], peer).unwrap();
+        device.send_to(&[6], peer).unwrap();
+        transport.set_timeout(Some(Duration::from_secs(5))).unwrap();
+
+        let mut buffer = [0; 2];
+        assert_eq!(transport.receive(&mut buffer).unwrap(), 2);
+        assert_eq!(buffer, [1, 2]);
+        assert_eq!(transport.receive(&mut buffer).unwrap(), 2);
+        assert_eq!(buffer, [3, 4]);
+        assert_eq!(transport.receive(&mut buffer).unwrap(), 1);
+        assert_eq!(buffer[0], 5);
+        assert_eq!(transport.receive(&mut buffer).unwrap(), 1);
+        assert_eq!(buffer[0], 6);
+    }
+
+    #[test]
+    fn ipv4() {
+        exchange("127.0.0.1:0");
+    }
+
+    #[test]
+    fn ipv6() {
+        exchange("[::1]:0");
+    }
+}
@@ -0,0 +1,396 @@
+//! Golden byte vectors of every instruction and addressing combination in Prism Binary Format
+//!
+//! Vectors are written by hand from the protobuf definitions: the instruction byte is the
+//! opcode shifted 2 bits to the left, bit 1 marks parameter A as indirect and bit 0 marks
+//! parameter B as indirect. Registers are written as their memory address ($n is 10 + n)
+
+use libnewton::addressing::AddressingMode;
+use libnewton::arrays::{Array2, Array3};
+use libnewton::codes::{DelayCode, EffectCode};
+use libnewton::error::Error;
+use libnewton::instruction::{InstructionSet, MAX_INSTRUCTION_LENGTH};
+use libnewton::registers::Register;
+
+fn imm(value: u8) -> AddressingMode {
+    AddressingMode::Immediate(value)
+}
+
+fn reg(register: Register) -> AddressingMode {
+    AddressingMode::Indirect(register)
+}
+
+fn gp(n: u8) -> Register {
+    Register::general(n).unwrap()
+}
+
+fn golden() -> Vec<(InstructionSet, Vec<u8>)> {
+    use InstructionSet::*;
+
+    vec![
+        // Instructions without operands
+        (NOP, vec![0x00]),
+        (BEGIN, vec![0x04]),
+        (RUN, vec![0x08]),
+        (TRANSMIT, vec![0x0C]),
+        (AIDX, vec![0x14]),
+        (RIDX, vec![0x18]),
+        (HOLD, vec![0x1C]),
+        (NHOLD, vec![0x20]),
+        (UPDATE, vec![0x24]),
+        (PAUSE, vec![0x74]),
+        (RESET, vec![0x7C]),
+        // A parameter only
+        (HALT(imm(7)), vec![0x10, 7]),
+        (HALT(reg(Register::SC)), vec![0x12, 0]),
+        (RET(imm(1)), vec![0x2C, 1]),
+        (RET(reg(gp(2))), vec![0x2E, 12]),
+        // Position (no addressing)
+        (JMP(0), vec![0x28, 0]),
+        (JMP(42), vec![0x28, 42]),
+        (JMP(255), vec![0x28, 255]),
+        // A and B parameters followed by a position
+        (BEQ(imm(1), imm(2), 9), vec![0x30, 1, 2, 9]),
+        (BEQ(imm(1), reg(gp(0)), 9), vec![0x31, 1, 10, 9]),
+        (BEQ(reg(Register::RV), imm(3), 9), vec![0x32, 5, 3, 9]),
+        (BEQ(reg(gp(0)), reg(gp(1)), 9), vec![0x33, 10, 11, 9]),
+        (BNE(imm(0), imm(255), 0), vec![0x34, 0, 255, 0]),
+        (BNE(imm(4), reg(Register::SF), 17), vec![0x35, 4, 1, 17]),
+        (BNE(reg(Register::PO), imm(0), 3), vec![0x36, 8, 0, 3]),
+        (BNE(reg(gp(30)), reg(gp(31)), 250), vec![0x37, 40, 41, 250]),
+        // Register (no addressing) and B parameter
+        (LOAD(gp(0), imm(5)), vec![0x40, 10, 5]),
+        (LOAD(gp(31), reg(Register::PO)), vec![0x41, 41, 8]),
+        (ADD(Register::SC, imm(1)), vec![0x44, 0, 1]),
+        (ADD(gp(1), reg(gp(2))), vec![0x45, 11, 12]),
+        (SUB(Register::R0, imm(255)), vec![0x48, 6, 255]),
+        (SUB(Register::R1, reg(Register::PC)), vec![0x49, 7, 3]),
+        // Range (A) and color (B)
+        (
+            FILL(
+                Array2(imm(0), imm(100)),
+                Array3(imm(120), imm(255), imm(128)),
+            ),
+            vec![0x4C, 0, 100, 120, 255, 128],
+        ),
+        (
+            FILL(
+                Array2(imm(0), imm(100)),
+                Array3(reg(gp(0)), reg(gp(1)), reg(gp(2))),
+            ),
+            vec![0x4D, 0, 100, 10, 11, 12],
+        ),
+        (
+            FILL(
+                Array2(reg(Register::R0), reg(Register::R1)),
+                Array3(imm(1), imm(2), imm(3)),
+            ),
+            vec![0x4E, 6, 7, 1, 2, 3],
+        ),
+        (
+            FILL(
+                Array2(reg(gp(0)), reg(gp(1))),
+                Array3(reg(gp(2)), reg(gp(3)), reg(gp(4))),
+            ),
+            vec![0x4F, 10, 11, 12, 13, 14],
+        ),
+        // Range (A) and a single component (B)
+        (
+            HFILL(Array2(imm(0), imm(50)), imm(10)),
+            vec![0x50, 0, 50, 10],
+        ),
+        (
+            HFILL(Array2(imm(0), imm(50)), reg(gp(0))),
+            vec![0x51, 0, 50, 10],
+        ),
+        (
+            HFILL(Array2(reg(gp(0)), reg(gp(1))), imm(10)),
+            vec![0x52, 10, 11, 10],
+        ),
+        (
+            HFILL(Array2(reg(gp(0)), reg(gp(1))), reg(gp(2))),
+            vec![0x53, 10, 11, 12],
+        ),
+        (
+            SFILL(Array2(imm(10), imm(20)), imm(30)),
+            vec![0x54, 10, 20, 30],
+        ),
+        (
+            SFILL(Array2(imm(10), imm(20)), reg(Register::RV)),
+            vec![0x55, 10, 20, 5],
+        ),
+        (
+            SFILL(Array2(reg(Register::SC), reg(Register::SF)), imm(30)),
+            vec![0x56, 0, 1, 30],
+        ),
+        (
+            SFILL(Array2(reg(gp(5)), reg(gp(6))), reg(gp(7))),
+            vec![0x57, 15, 16, 17],
+        ),
+        (LFILL(Array2(imm(1), imm(2)), imm(3)), vec![0x58, 1, 2, 3]),
+        (
+            LFILL(Array2(imm(1), imm(2)), reg(gp(9))),
+            vec![0x59, 1, 2, 19],
+        ),
+        (
+            LFILL(Array2(reg(gp(10)), reg(gp(11))), imm(3)),
+            vec![0x5A, 20, 21, 3],
+        ),
+        (
+            LFILL(Array2(reg(Register::PP), reg(Register::PC)), reg(gp(0))),
+            vec![0x5B, 4, 3, 10],
+        ),
+        // Index (A) and color (B)
+        (
+            PAINT(imm(3), Array3(imm(1), imm(2), imm(3))),
+            vec![0x5C, 3, 1, 2, 3],
+        ),
+        (
+            PAINT(imm(3), Array3(reg(gp(0)), reg(gp(1)), reg(gp(2)))),
+            vec![0x5D, 3, 10, 11, 12],
+        ),
+        (
+            PAINT(reg(gp(0)), Array3(imm(1), imm(2), imm(3))),
+            vec![0x5E, 10, 1, 2, 3],
+        ),
+        (
+            PAINT(reg(gp(0)), Array3(reg(gp(1)), reg(gp(2)), reg(gp(3)))),
+            vec![0x5F, 10, 11, 12, 13],
+        ),
+        // Index (A) and a single component (B)
+        (HPAINT(imm(1), imm(2)), vec![0x60, 1, 2]),
+        (HPAINT(imm(1), reg(gp(2))), vec![0x61, 1, 12]),
+        (HPAINT(reg(gp(1)), imm(2)), vec![0x62, 11, 2]),
+        (HPAINT(reg(gp(1)), reg(gp(2))), vec![0x63, 11, 12]),
+        (SPAINT(imm(4), imm(5)), vec![0x64, 4, 5]),
+        (SPAINT(imm(4), reg(Register::R0)), vec![0x65, 4, 6]),
+        (SPAINT(reg(Register::R1), imm(5)), vec![0x66, 7, 5]),
+        (SPAINT(reg(gp(20)), reg(gp(21))), vec![0x67, 30, 31]),
+        (LPAINT(imm(0), imm(255)), vec![0x68, 0, 255]),
+        (LPAINT(imm(0), reg(Register::SC)), vec![0x69, 0, 0]),
+        (LPAINT(reg(Register::PO), imm(255)), vec![0x6A, 8, 255]),
+        (LPAINT(reg(gp(3)), reg(gp(4))), vec![0x6B, 13, 14]),
+        // Effect code (no addressing), range (A) and value (B)
+        (
+            EFFECT(EffectCode::DIM, Array2(imm(0), imm(100)), imm(20)),
+            vec![0x6C, 0, 0, 100, 20],
+        ),
+        (
+            EFFECT(EffectCode::BLEND, Array2(imm(0), imm(100)), reg(gp(0))),
+            vec![0x6D, 1, 0, 100, 10],
+        ),
+        (
+            EFFECT(
+                EffectCode::DIM,
+                Array2(reg(Register::R0), reg(Register::R1)),
+                imm(5),
+            ),
+            vec![0x6E, 0, 6, 7, 5],
+        ),
+        (
+            EFFECT(
+                EffectCode::Custom(7),
+                Array2(reg(gp(0)), reg(gp(1))),
+                reg(gp(2)),
+            ),
+            vec![0x6F, 7, 10, 11, 12],
+        ),
+        // Delay code (no addressing) and amount (A)
+        (DELAY(DelayCode::MS, imm(200)), vec![0x70, 0, 200]),
+        (DELAY(DelayCode::SEC, reg(gp(0))), vec![0x72, 1, 10]),
+        (DELAY(DelayCode::MIN, imm(1)), vec![0x70, 2, 1]),
+        (DELAY(DelayCode::HRS, reg(Register::RV)), vec![0x72, 3, 5]),
+        // Register (no addressing)
+        (GET(Register::SC), vec![0x78, 0]),
+        (GET(Register::SF), vec![0x78, 1]),
+        (GET(Register::PC), vec![0x78, 3]),
+        (GET(Register::PP), vec![0x78, 4]),
+        (GET(Register::RV), vec![0x78, 5]),
+        (GET(Register::R0), vec![0x78, 6]),
+        (GET(Register::R1), vec![0x78, 7]),
+        (GET(Register::PO), vec![0x78, 8]),
+        (GET(gp(0)), vec![0x78, 10]),
+        (GET(gp(31)), vec![0x78, 41]),
+    ]
+}
+
+#[test]
+fn encode_matches_golden_bytes() {
+    for (instruction, bytes) in golden() {
+        assert_eq!(instruction.encode().unwrap(), bytes, "{:?}", instruction);
+    }
+}
+
+#[test]
+fn decode_matches_golden_bytes() {
+    for (instruction, bytes) in golden() {
+        assert_eq!(
+            InstructionSet::decode(&bytes).unwrap(),
+            (instruction, bytes.len()),
+            "{:02x?}",
+            bytes
+        );
+    }
+}
+
+#[test]
+fn encode_into_matches_golden_bytes() {
+    for (instruction, bytes) in golden() {
+        let mut buffer = [0; MAX_INSTRUCTION_LENGTH];
+        let length = instruction.encode_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[..length], bytes, "{:?}", instruction);
+    }
+}
+
+#[test]
+fn size_matches_golden_length() {
+    for (instruction, bytes) in golden() {
+        assert_eq!(instruction.size(), bytes.len(), "{:?}", instruction);
+    }
+}
+
+#[test]
+fn golden_covers_every_opcode() {
+    let mut opcodes: Vec<u8> = golden().iter().map(|(_, bytes)| bytes[0] >> 2).collect();
+    opcodes.sort();
+    opcodes.dedup();
+
+    let expected: Vec<u8> = (0..=31)
+        .filter(|opcode| !matches!(opcode, 14 | 15))
+        .collect();
+    assert_eq!(opcodes, expected);
+}
+
+#[test]
+fn decode_ignores_trailing_bytes() {
+    assert_eq!(
+        InstructionSet::decode(&[0x28, 42, 0x00, 0x04]).unwrap(),
+        (InstructionSet::JMP(42), 2)
+    );
+}
+
+#[test]
+fn encode_rejects_mixed_addressing() {
+    let range = InstructionSet::HFILL(Array2(imm(0), reg(gp(0))), imm(0));
+    assert_eq!(range.encode(), Err(Error::MixedAddressing));
+
+    let color = InstructionSet::PAINT(imm(0), Array3(imm(120), imm(255), reg(gp(2))));
+    assert_eq!(color.encode(), Err(Error::MixedAddressing));
+}
+
+#[test]
+fn encode_rejects_standard_codes_as_custom_effects() {
+    for code in [0, 1] {
+        let effect =
+            InstructionSet::EFFECT(EffectCode::Custom(code), Array2(imm(0), imm(100)), imm(0));
+        assert_eq!(effect.encode(), Err(Error::StandardEffectCode(code)));
+    }
+}
+
+#[test]
+fn encode_into_rejects_small_buffers() {
+    let mut buffer = [0; 5];
+    let fill = InstructionSet::FILL(Array2(imm(0), imm(100)), Array3(imm(1), imm(2), imm(3)));
+    assert_eq!(
+        fill.encode_into(&mut buffer),
+        Err(Error::Truncated {
+            expected: 6,
+            available: 5
+        })
+    );
+}
+
+#[test]
+fn decode_rejects_unknown_opcodes() {
+    assert_eq!(
+        InstructionSet::decode(&[0x38]),
+        Err(Error::UnknownOpcode(14))
+    );
+    assert_eq!(
+        InstructionSet::decode(&[0x3F]),
+        Err(Error::UnknownOpcode(15))
+    );
+    assert_eq!(
+        InstructionSet::decode(&[0xFF]),
+        Err(Error::UnknownOpcode(63))
+    );
+}
+
+#[test]
+fn decode_rejects_truncated_instructions() {
+    assert_eq!(
+        InstructionSet::decode(&[]),
+        Err(Error::Truncated {
+            expected: 1,
+            available: 0
+        })
+    );
+    assert_eq!(
+        InstructionSet::decode(&[0x4C, 0, 100, 120]),
+        Err(Error::Truncated {
+            expected: 6,
+            available: 4
+        })
+    );
+}
+
+#[test]
+fn decode_rejects_invalid_operands() {
+    // Address 2 and 9 are reserved, general purpose registers end at 41 ($31)
+    assert_eq!(
+        InstructionSet::decode(&[0x78, 2]),
+        Err(Error::InvalidRegister(2))
+    );
+    assert_eq!(
+        InstructionSet::decode(&[0x40, 9, 0]),
+        Err(Error::InvalidRegister(9))
+    );
+    assert_eq!(
+        InstructionSet::decode(&[0x12, 42]),
+        Err(Error::InvalidRegister(42))
+    );
+    assert_eq!(
+        InstructionSet::decode(&[0x70, 4, 0]),
+        Err(Error::InvalidDelayCode(4))
+    );
+}
+
+#[test]
+fn decode_rejects_addressing_of_missing_parameters() {
+    let vectors: [&[u8]; 14] = [
+        // No parameters
+        &[0x01],
+        &[0x02],
+        &[0x07],
+        &[0x7F],
+        // No A parameter
+        &[0x42, 10, 5],
+        &[0x46, 11, 12],
+        &[0x4A, 6, 255],
+        &[0x2A, 42],
+        &[0x7A, 10],
+        // No B parameter
+        &[0x11, 7],
+        &[0x2D, 1],
+        &[0x71, 0, 200],
+        &[0x29, 42],
+        &[0x79, 10],
+    ];
+
+    for bytes in vectors {
+        assert_eq!(
+            InstructionSet::decode(bytes),
+            Err(Error::InvalidAddressing(bytes[0])),
+            "{:02x?}",
+            bytes
+        );
+    }
+}
+
+#[test]
+fn standard_effect_codes_are_decoded_as_standard_effects() {
+    let (instruction, _) = InstructionSet::decode(&[0x6C, 1, 0, 100, 20]).unwrap();
+    assert_eq!(
+        instruction,
+        InstructionSet::EFFECT(EffectCode::BLEND, Array2(imm(0), imm(100)), imm(20))
+    );
+}
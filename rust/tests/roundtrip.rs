@@ -0,0 +1,191 @@
+//! Property tests, arbitrary valid instructions must survive encoding and decoding unchanged
+
+use proptest::prelude::*;
+use proptest::strategy::Union;
+
+use libnewton::addressing::AddressingMode;
+use libnewton::arrays::{Array2, Array3};
+use libnewton::assembler;
+use libnewton::binary;
+use libnewton::codes::{DelayCode, EffectCode};
+use libnewton::instruction::{InstructionSet, MAX_INSTRUCTION_LENGTH};
+use libnewton::registers::Register;
+use libnewton::stream::StreamDecoder;
+
+fn register() -> impl Strategy<Value = Register> {
+    prop_oneof![
+        Just(Register::SC),
+        Just(Register::SF),
+        Just(Register::PC),
+        Just(Register::PP),
+        Just(Register::RV),
+        Just(Register::R0),
+        Just(Register::R1),
+        Just(Register::PO),
+        (0..32u8).prop_map(|n| Register::general(n).unwrap()),
+    ]
+}
+
+fn addressing() -> impl Strategy<Value = AddressingMode> {
+    prop_oneof![
+        any::<u8>().prop_map(AddressingMode::Immediate),
+        register().prop_map(AddressingMode::Indirect),
+    ]
+}
+
+/// Every element shares the same addressing mode
+fn array2() -> impl Strategy<Value = Array2<AddressingMode>> {
+    prop_oneof![
+        any::<[u8; 2]>()
+            .prop_map(|[a, b]| Array2(AddressingMode::Immediate(a), AddressingMode::Immediate(b))),
+        (register(), register())
+            .prop_map(|(a, b)| Array2(AddressingMode::Indirect(a), AddressingMode::Indirect(b))),
+    ]
+}
+
+/// Every element shares the same addressing mode
+fn array3() -> impl Strategy<Value = Array3<AddressingMode>> {
+    prop_oneof![
+        any::<[u8; 3]>().prop_map(|[a, b, c]| Array3(
+            AddressingMode::Immediate(a),
+            AddressingMode::Immediate(b),
+            AddressingMode::Immediate(c)
+        )),
+        (register(), register(), register()).prop_map(|(a, b, c)| Array3(
+            AddressingMode::Indirect(a),
+            AddressingMode::Indirect(b),
+            AddressingMode::Indirect(c)
+        )),
+    ]
+}
+
+fn effect() -> impl Strategy<Value = EffectCode> {
+    any::<u8>().prop_map(EffectCode::from)
+}
+
+fn delay() -> impl Strategy<Value = DelayCode> {
+    prop_oneof![
+        Just(DelayCode::MS),
+        Just(DelayCode::SEC),
+        Just(DelayCode::MIN),
+        Just(DelayCode::HRS),
+    ]
+}
+
+fn instruction() -> impl Strategy<Value = InstructionSet> {
+    use InstructionSet::*;
+
+    Union::new(vec![
+        prop_oneof![
+            Just(NOP),
+            Just(BEGIN),
+            Just(RUN),
+            Just(TRANSMIT),
+            Just(AIDX),
+            Just(RIDX),
+            Just(HOLD),
+            Just(NHOLD),
+            Just(UPDATE),
+            Just(PAUSE),
+            Just(RESET),
+        ]
+        .boxed(),
+        addressing().prop_map(HALT).boxed(),
+        addressing().prop_map(RET).boxed(),
+        any::<u8>().prop_map(JMP).boxed(),
+        (addressing(), addressing(), any::<u8>())
+            .prop_map(|(a, b, position)| BEQ(a, b, position))
+            .boxed(),
+        (addressing(), addressing(), any::<u8>())
+            .prop_map(|(a, b, position)| BNE(a, b, position))
+            .boxed(),
+        (register(), addressing())
+            .prop_map(|(r, b)| LOAD(r, b))
+            .boxed(),
+        (register(), addressing())
+            .prop_map(|(r, b)| ADD(r, b))
+            .boxed(),
+        (register(), addressing())
+            .prop_map(|(r, b)| SUB(r, b))
+            .boxed(),
+        (array2(), array3())
+            .prop_map(|(range, color)| FILL(range, color))
+            .boxed(),
+        (array2(), addressing())
+            .prop_map(|(range, value)| HFILL(range, value))
+            .boxed(),
+        (array2(), addressing())
+            .prop_map(|(range, value)| SFILL(range, value))
+            .boxed(),
+        (array2(), addressing())
+            .prop_map(|(range, value)| LFILL(range, value))
+            .boxed(),
+        (addressing(), array3())
+            .prop_map(|(index, color)| PAINT(index, color))
+            .boxed(),
+        (addressing(), addressing())
+            .prop_map(|(index, value)| HPAINT(index, value))
+            .boxed(),
+        (addressing(), addressing())
+            .prop_map(|(index, value)| SPAINT(index, value))
+            .boxed(),
+        (addressing(), addressing())
+            .prop_map(|(index, value)| LPAINT(index, value))
+            .boxed(),
+        (effect(), array2(), addressing())
+            .prop_map(|(code, range, value)| EFFECT(code, range, value))
+            .boxed(),
+        (delay(), addressing())
+            .prop_map(|(code, amount)| DELAY(code, amount))
+            .boxed(),
+        register().prop_map(GET).boxed(),
+    ])
+}
+
+proptest! {
+    #[test]
+    fn decode_inverts_encode(instruction in instruction()) {
+        let bytes = instruction.encode().unwrap();
+        prop_assert_eq!(bytes.len(), instruction.size());
+        prop_assert_eq!(InstructionSet::decode(&bytes).unwrap(), (instruction, bytes.len()));
+    }
+
+    #[test]
+    fn encode_into_matches_encode(instruction in instruction()) {
+        let mut buffer = [0; MAX_INSTRUCTION_LENGTH];
+        let length = instruction.encode_into(&mut buffer).unwrap();
+        let bytes = instruction.encode().unwrap();
+        prop_assert_eq!(&buffer[..length], bytes.as_slice());
+    }
+
+    #[test]
+    fn disassemble_inverts_assemble(instructions in prop::collection::vec(instruction(), 0..64)) {
+        let bytes = binary::assemble(&instructions).unwrap();
+        prop_assert_eq!(binary::disassemble(&bytes).unwrap(), instructions);
+    }
+
+    #[test]
+    fn stream_decoder_inverts_assemble(
+        instructions in prop::collection::vec(instruction(), 0..64),
+        chunk in 1..16usize,
+    ) {
+        let bytes = binary::assemble(&instructions).unwrap();
+
+        let mut decoder = StreamDecoder::new();
+        let mut decoded = vec![];
+        for chunk in bytes.chunks(chunk) {
+            for instruction in decoder.feed(chunk) {
+                decoded.push(instruction.unwrap());
+            }
+        }
+
+        prop_assert!(decoder.is_empty());
+        prop_assert_eq!(decoded, instructions);
+    }
+
+    #[test]
+    fn parse_inverts_display(instruction in instruction()) {
+        let source = instruction.to_string();
+        prop_assert_eq!(assembler::parse(&source).unwrap(), vec![instruction]);
+    }
+}